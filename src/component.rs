@@ -0,0 +1,122 @@
+//! Reusable parts of applications, composed like in the Elm architecture.
+//!
+//! A `Component` has actions, tasks and a view of its own, just like an `Application`. An
+//! application embedding one keeps it in a field and wraps its actions and tasks into its
+//! own, e.g. as `Action::Picker(picker::Action)`:
+//!
+//! - key events meant for the component are converted with `From<rustty::Event>` and passed on
+//!   in `handle_action`, using `update` to wrap the task returned;
+//! - actions the component sends itself arrive through a handle created by `LoopHandle::map`;
+//! - wrapped tasks are passed back to `Component::exec_task`;
+//! - the view is drawn with `Component::view`, or placed into layouts as a `ComponentView`.
+//!
+//! This way the component can be embedded in many applications without them knowing its
+//! internals, and tested on its own by running it as a `Root`.
+
+use rustty;
+use view::{DrawingContext, Widget};
+use view::theme::Theme;
+use {Application, LoopHandle};
+
+/// A part of an application with its own actions, tasks and view.
+///
+/// The methods have the same meaning as those of `Application`, with the actions and tasks
+/// of the component instead of those of the application.
+pub trait Component: Sized {
+    type Action: From<rustty::Event> + Send + 'static;
+    type Task;
+
+    fn handle_action(self, action: Self::Action) -> (Self, Option<Self::Task>);
+
+    /// Runs a task returned by `handle_action`. Returns `true` to quit the application.
+    fn exec_task(&self, task: Self::Task) -> bool;
+
+    fn view(&self, ctx: &mut DrawingContext);
+
+    /// Called with a handle delivering actions to the component, when the application is
+    /// initialized. Embedding applications create it with `LoopHandle::map`.
+    fn init(&mut self, _handle: LoopHandle<Self::Action>) {}
+}
+
+/// Passes `action` to `component`, wrapping the task it returns with `f` into a task of the
+/// embedding application. Like `Cmd.map` in Elm.
+pub fn update<C, T, F>(component: C, action: C::Action, f: F) -> (C, Option<T>)
+    where C: Component,
+          F: FnOnce(C::Task) -> T
+{
+    let (component, task) = component.handle_action(action);
+    (component, task.map(f))
+}
+
+/// Draws a component as a widget, e.g. inside of a layout.
+pub struct ComponentView<'a, C: 'a> {
+    component: &'a C,
+}
+
+impl<'a, C: Component> ComponentView<'a, C> {
+    pub fn new(component: &'a C) -> ComponentView<'a, C> {
+        ComponentView { component }
+    }
+}
+
+impl<'a, C: Component> Widget for ComponentView<'a, C> {
+    fn draw_on(&self, ctx: &mut DrawingContext) {
+        self.component.view(ctx)
+    }
+}
+
+/// Runs a component as the whole application, e.g. to try it out on its own.
+pub struct Root<C> {
+    component: C,
+    theme: Option<Theme>,
+}
+
+impl<C: Component> Root<C> {
+    pub fn new(component: C) -> Root<C> {
+        Root {
+            component,
+            theme: None,
+        }
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Root<C> {
+        self.theme = Some(theme);
+        self
+    }
+
+    #[inline]
+    pub fn component(&self) -> &C {
+        &self.component
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> C {
+        self.component
+    }
+}
+
+impl<C: Component> Application for Root<C> {
+    type Action = C::Action;
+    type Task = C::Task;
+
+    fn handle_action(self, action: C::Action) -> (Self, Option<C::Task>) {
+        let (component, task) = self.component.handle_action(action);
+        (Root { component, ..self }, task)
+    }
+
+    fn exec_task(&self, task: C::Task) -> bool {
+        self.component.exec_task(task)
+    }
+
+    fn view(&self, ctx: &mut DrawingContext) {
+        self.component.view(ctx)
+    }
+
+    fn init(&mut self, handle: LoopHandle<C::Action>) {
+        self.component.init(handle)
+    }
+
+    fn theme(&self) -> Option<&Theme> {
+        self.theme.as_ref()
+    }
+}
@@ -0,0 +1,336 @@
+//! Themes and key bindings loaded from configuration files.
+//!
+//! The file format is a subset of TOML. Styles of the `[theme]` section are written as
+//! understood by `view::theme::parse_style`, key sections are named after the `Keymap` they
+//! change and bind key sequences to action names:
+//!
+//! ```toml
+//! [theme]
+//! title = "bold yellow on blue"
+//! list.cursor = "reverse"
+//!
+//! [keys.global]
+//! q = "quit"
+//! "C-x C-s" = "save"
+//! "g g" = ""          # removes the binding
+//! ```
+
+use keymap::{KeySeq, Keymap};
+use std::{fmt, fs, io, thread};
+use std::cmp::max;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use view::theme::{parse_style, Theme};
+use {LoopHandle, TaskHandle};
+
+/// Error loading a configuration file.
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    /// Invalid contents, with the number of the offending line, starting at 1.
+    Invalid { line: usize, message: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::Io(ref err) => err.fmt(f),
+            ConfigError::Invalid { line, ref message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            ConfigError::Io(ref err) => Some(err),
+            ConfigError::Invalid { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(err: io::Error) -> ConfigError {
+        ConfigError::Io(err)
+    }
+}
+
+#[inline]
+fn invalid<T, S: Into<String>>(line: usize, message: S) -> Result<T, ConfigError> {
+    Err(ConfigError::Invalid {
+        line,
+        message: message.into(),
+    })
+}
+
+/// A key sequence bound to an action name.
+#[derive(Clone, Debug)]
+pub struct KeyEntry {
+    pub keys: KeySeq,
+    /// Name of the action, or empty to remove the binding.
+    pub action: String,
+    pub line: usize,
+}
+
+/// Contents of a configuration file.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Only contains the rules from the file, to be merged into the application's theme.
+    pub theme: Theme,
+    keys: Vec<(String, Vec<KeyEntry>)>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            theme: Theme::empty(),
+            keys: Vec::new(),
+        }
+    }
+}
+
+/// Splits a quoted string off the start of `s`, returning its contents and the rest.
+fn split_quoted(s: &str, line: usize) -> Result<(String, &str), ConfigError> {
+    let mut value = String::new();
+    let mut chars = s.char_indices().skip(1);
+
+    while let Some((i, ch)) = chars.next() {
+        match ch {
+            '"' => return Ok((value, &s[i + 1..])),
+            '\\' => {
+                value.push(match chars.next() {
+                    Some((_, '"')) => '"',
+                    Some((_, '\\')) => '\\',
+                    Some((_, 't')) => '\t',
+                    Some((_, 'n')) => '\n',
+                    Some((_, other)) => {
+                        return invalid(line, format!("unknown escape \\{}", other))
+                    }
+                    None => break,
+                });
+            }
+            _ => value.push(ch),
+        }
+    }
+
+    invalid(line, "unterminated string")
+}
+
+/// Splits a bare or quoted key off the start of `s`.
+fn split_key(s: &str, line: usize) -> Result<(String, &str), ConfigError> {
+    if s.starts_with('"') {
+        return split_quoted(s, line);
+    }
+
+    let end = s.find(|c: char| !(c.is_ascii_alphanumeric() || "_-.".contains(c)))
+        .unwrap_or(s.len());
+    if end == 0 {
+        return invalid(line, "expected a key");
+    }
+    Ok((s[..end].to_owned(), &s[end..]))
+}
+
+/// Checks that only whitespace or a comment follows.
+fn expect_end(rest: &str, line: usize) -> Result<(), ConfigError> {
+    let rest = rest.trim_start();
+    if rest.is_empty() || rest.starts_with('#') {
+        Ok(())
+    } else {
+        invalid(line, format!("unexpected {:?}", rest))
+    }
+}
+
+impl Config {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Config, ConfigError> {
+        Config::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(src: &str) -> Result<Config, ConfigError> {
+        enum Section {
+            None,
+            Theme,
+            Keys(usize),
+        }
+
+        let mut config = Config::default();
+        let mut section = Section::None;
+        let mut seen: Vec<String> = Vec::new();
+
+        for (i, text) in src.lines().enumerate() {
+            let line = i + 1;
+            let text = text.trim();
+
+            if text.is_empty() || text.starts_with('#') {
+                continue;
+            }
+
+            if text.starts_with('[') {
+                let end = match text.find(']') {
+                    Some(end) => end,
+                    None => return invalid(line, "expected \"]\""),
+                };
+                expect_end(&text[end + 1..], line)?;
+
+                let name = text[1..end].trim();
+                if seen.iter().any(|s| s == name) {
+                    return invalid(line, format!("section [{}] appears twice", name));
+                }
+                seen.push(name.to_owned());
+
+                section = if name == "theme" {
+                    Section::Theme
+                } else if name.starts_with("keys.") && name.len() > 5 {
+                    config.keys.push((name[5..].to_owned(), Vec::new()));
+                    Section::Keys(config.keys.len() - 1)
+                } else {
+                    return invalid(line, format!("unknown section [{}]", name));
+                };
+                continue;
+            }
+
+            let (key, rest) = split_key(text, line)?;
+            let rest = rest.trim_start();
+            if !rest.starts_with('=') {
+                return invalid(line, "expected \"=\"");
+            }
+            let rest = rest[1..].trim_start();
+            if !rest.starts_with('"') {
+                return invalid(line, "expected a quoted string");
+            }
+            let (value, rest) = split_quoted(rest, line)?;
+            expect_end(rest, line)?;
+
+            match section {
+                Section::None => return invalid(line, "entry outside of a section"),
+                Section::Theme => {
+                    if config.theme.rule(&key).is_some() {
+                        return invalid(line, format!("style {:?} is set twice", key));
+                    }
+                    match parse_style(&value) {
+                        Ok(style) => config.theme.set(&key, style),
+                        Err(message) => return invalid(line, message),
+                    }
+                }
+                Section::Keys(idx) => {
+                    let keys: KeySeq = match key.parse() {
+                        Ok(keys) => keys,
+                        Err(err) => return invalid(line, err.to_string()),
+                    };
+
+                    let entries = &mut config.keys[idx].1;
+                    if let Some(prev) = entries.iter().find(|e| e.keys == keys) {
+                        return invalid(line,
+                                       format!("{} is already bound on line {}", keys, prev.line));
+                    }
+                    entries.push(KeyEntry {
+                        keys,
+                        action: value,
+                        line,
+                    });
+                }
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// The key entries for the keymap called `name`.
+    pub fn keys(&self, name: &str) -> &[KeyEntry] {
+        self.keys
+            .iter()
+            .find(|&(n, _)| n == name)
+            .map_or(&[], |(_, entries)| &entries[..])
+    }
+
+    /// Changes `keymap` according to the section of the same name.
+    ///
+    /// `action_named` looks up actions by name; unknown names are reported as error. The help
+    /// text of an added binding is taken from an existing binding of the same action.
+    pub fn apply_keys<A, F>(&self,
+                            keymap: &mut Keymap<A>,
+                            mut action_named: F)
+                            -> Result<(), ConfigError>
+        where A: Clone + PartialEq,
+              F: FnMut(&str) -> Option<A>
+    {
+        // resolve everything first, so an error leaves the keymap unchanged
+        let mut changes = Vec::new();
+        for entry in self.keys(keymap.name()) {
+            if entry.action.is_empty() {
+                changes.push((&entry.keys, None));
+                continue;
+            }
+
+            match action_named(&entry.action) {
+                Some(action) => changes.push((&entry.keys, Some(action))),
+                None => return invalid(entry.line, format!("unknown action {:?}", entry.action)),
+            }
+        }
+
+        for (keys, action) in changes {
+            keymap.unbind(keys);
+
+            if let Some(action) = action {
+                let help = keymap.bindings()
+                    .iter()
+                    .find(|b| *b.action() == action)
+                    .map(|b| b.help().to_owned())
+                    .unwrap_or_default();
+                keymap.insert(keys.clone(), action, &help);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Shortest interval `watch` checks the file in.
+const MIN_WATCH_INTERVAL: Duration = Duration::from_millis(100);
+
+#[inline]
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Reloads the configuration file at `path` whenever it changes, delivering the action
+/// created from the result.
+///
+/// The file is checked every `interval`, but at most every 100 ms, on a thread of its own
+/// until the returned handle is cancelled or the main loop exits.
+pub fn watch<A, F>(handle: &LoopHandle<A>,
+                   path: PathBuf,
+                   interval: Duration,
+                   mut on_change: F)
+                   -> TaskHandle
+    where A: Send + 'static,
+          F: FnMut(Result<Config, ConfigError>) -> A + Send + 'static
+{
+    let handle = handle.clone();
+    let interval = max(interval, MIN_WATCH_INTERVAL);
+    let task = TaskHandle::new();
+    let task_handle = task.clone();
+
+    thread::spawn(move || {
+        let mut last = modified(&path);
+
+        loop {
+            thread::sleep(interval);
+            if task_handle.is_cancelled() || handle.is_closed() {
+                break;
+            }
+
+            let current = modified(&path);
+            if current == last {
+                continue;
+            }
+            last = current;
+
+            if !handle.send(on_change(Config::load(&path))) {
+                break;
+            }
+        }
+    });
+
+    task
+}
@@ -0,0 +1,124 @@
+use std::collections::VecDeque;
+use std::panic;
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+pub(crate) type Job = Box<dyn FnOnce() + Send>;
+
+/// Cancellation handle of a task started with `LoopHandle::spawn`.
+///
+/// The same handle is passed to the running task, which can check `is_cancelled` to stop
+/// early. Results of cancelled tasks are discarded.
+#[derive(Clone, Debug, Default)]
+pub struct TaskHandle {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl TaskHandle {
+    pub(crate) fn new() -> TaskHandle {
+        TaskHandle::default()
+    }
+
+    #[inline]
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+struct Queue {
+    jobs: VecDeque<Job>,
+    workers: usize,
+    idle: usize,
+    shutdown: bool,
+}
+
+/// A pool of worker threads, started on demand up to a fixed maximum.
+#[derive(Clone)]
+pub(crate) struct Executor {
+    max_workers: usize,
+    shared: Arc<(Mutex<Queue>, Condvar)>,
+}
+
+impl Executor {
+    pub fn new(max_workers: usize) -> Executor {
+        Executor {
+            max_workers: max_workers.max(1),
+            shared: Arc::new((Mutex::new(Queue {
+                                  jobs: VecDeque::new(),
+                                  workers: 0,
+                                  idle: 0,
+                                  shutdown: false,
+                              }),
+                              Condvar::new())),
+        }
+    }
+
+    pub fn submit(&self, job: Job) {
+        let (ref lock, ref cvar) = *self.shared;
+        let mut queue = lock.lock().unwrap();
+
+        if queue.shutdown {
+            return;
+        }
+
+        queue.jobs.push_back(job);
+
+        // every idle worker takes one job, start another one for the remainder
+        if queue.jobs.len() > queue.idle && queue.workers < self.max_workers {
+            queue.workers += 1;
+            let shared = self.shared.clone();
+            thread::spawn(move || work(shared));
+        }
+
+        cvar.notify_one();
+    }
+
+    /// Whether `shutdown` was called, i.e. the main loop has exited.
+    pub fn is_shutdown(&self) -> bool {
+        self.shared.0.lock().unwrap().shutdown
+    }
+
+    /// Stops all workers once they finish their current job. Queued jobs are dropped.
+    pub fn shutdown(&self) {
+        let (ref lock, ref cvar) = *self.shared;
+        let mut queue = lock.lock().unwrap();
+
+        queue.shutdown = true;
+        queue.jobs.clear();
+        cvar.notify_all();
+    }
+}
+
+fn work(shared: Arc<(Mutex<Queue>, Condvar)>) {
+    let (ref lock, ref cvar) = *shared;
+
+    loop {
+        let job = {
+            let mut queue = lock.lock().unwrap();
+
+            loop {
+                if queue.shutdown {
+                    queue.workers -= 1;
+                    return;
+                }
+
+                if let Some(job) = queue.jobs.pop_front() {
+                    break job;
+                }
+
+                queue.idle += 1;
+                queue = cvar.wait(queue).unwrap();
+                queue.idle -= 1;
+            }
+        };
+
+        // a panicking task must not take the worker down with it
+        let _ = panic::catch_unwind(panic::AssertUnwindSafe(job));
+    }
+}
@@ -0,0 +1,50 @@
+use std::time::{Duration, Instant};
+
+/// Decides when the main loop redraws.
+///
+/// Redraw requests are coalesced: any number of requests between two frames results in a
+/// single redraw. If a request arrives before `min_delay` has passed since the last frame, the
+/// redraw is deferred rather than dropped, so the final state is always shown.
+pub(crate) struct FrameScheduler {
+    min_delay: Option<Duration>,
+    last_draw: Instant,
+    pending: bool,
+}
+
+impl FrameScheduler {
+    pub fn new(min_delay: Option<Duration>) -> FrameScheduler {
+        FrameScheduler {
+            min_delay,
+            last_draw: Instant::now(),
+            pending: false,
+        }
+    }
+
+    #[inline]
+    pub fn request(&mut self) {
+        self.pending = true;
+    }
+
+    /// Returns the time the next frame should be drawn at, if a redraw is pending.
+    pub fn next_frame(&self) -> Option<Instant> {
+        if !self.pending {
+            return None;
+        }
+
+        Some(match self.min_delay {
+            Some(delay) => self.last_draw + delay,
+            None => self.last_draw,
+        })
+    }
+
+    #[inline]
+    pub fn is_due(&self, now: Instant) -> bool {
+        self.next_frame().is_some_and(|at| at <= now)
+    }
+
+    #[inline]
+    pub fn drawn(&mut self, now: Instant) {
+        self.last_draw = now;
+        self.pending = false;
+    }
+}
@@ -0,0 +1,217 @@
+use std::io;
+use std::process::{Command, ExitStatus};
+use std::sync::{self, mpsc};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use executor::{Executor, TaskHandle};
+use timer::{ActionFn, Schedule, TimerId};
+
+/// Function run on the main loop while the terminal is released.
+pub(crate) type ReleaseFn<A> = Box<dyn FnOnce() -> A + Send>;
+
+/// Messages received by the main loop.
+pub(crate) enum Message<A> {
+    Action(A),
+    AddTimer(TimerId, Schedule, ActionFn<A>),
+    CancelTimer(TimerId),
+    Redraw,
+    Resize,
+    Quit,
+    Suspend,
+    Resume,
+    Release(ReleaseFn<A>),
+}
+
+impl<B: 'static> Message<B> {
+    /// Converts the actions carried by the message, now or once they are created.
+    fn map<A, F>(self, f: &sync::Arc<F>) -> Message<A>
+        where F: Fn(B) -> A + Send + Sync + 'static
+    {
+        match self {
+            Message::Action(action) => Message::Action(f(action)),
+            Message::AddTimer(id, schedule, mut make_action) => {
+                let f = f.clone();
+                Message::AddTimer(id, schedule, Box::new(move || f(make_action())))
+            }
+            Message::CancelTimer(id) => Message::CancelTimer(id),
+            Message::Redraw => Message::Redraw,
+            Message::Resize => Message::Resize,
+            Message::Quit => Message::Quit,
+            Message::Suspend => Message::Suspend,
+            Message::Resume => Message::Resume,
+            Message::Release(release) => {
+                let f = f.clone();
+                Message::Release(Box::new(move || f(release())))
+            }
+        }
+    }
+}
+
+/// Delivers a message to the main loop, returning `false` if it has exited.
+type SendFn<A> = sync::Arc<dyn Fn(Message<A>) -> bool + Send + Sync>;
+
+/// Handle to a running `MainLoop`.
+///
+/// Handles are cheap to clone and can be moved to other threads. An application receives one
+/// in `Application::init` and can keep it around to schedule timers or start background tasks
+/// from `handle_action` or `exec_task`.
+pub struct LoopHandle<A> {
+    sender: SendFn<A>,
+    next_id: sync::Arc<AtomicUsize>,
+    executor: Executor,
+}
+
+impl<A> Clone for LoopHandle<A> {
+    fn clone(&self) -> Self {
+        LoopHandle {
+            sender: self.sender.clone(),
+            next_id: self.next_id.clone(),
+            executor: self.executor.clone(),
+        }
+    }
+}
+
+impl<A: Send + 'static> LoopHandle<A> {
+    pub(crate) fn new(sender: mpsc::Sender<Message<A>>, executor: Executor) -> LoopHandle<A> {
+        LoopHandle {
+            sender: sync::Arc::new(move |msg| sender.send(msg).is_ok()),
+            next_id: sync::Arc::new(AtomicUsize::new(0)),
+            executor,
+        }
+    }
+
+    /// Delivers `action` to the application. Returns `false` if the main loop has exited.
+    pub fn send(&self, action: A) -> bool {
+        (self.sender)(Message::Action(action))
+    }
+
+    /// Whether the main loop has exited, e.g. for threads to stop delivering actions.
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.executor.is_shutdown()
+    }
+
+    pub(crate) fn send_resize(&self) {
+        (self.sender)(Message::Resize);
+    }
+
+    pub(crate) fn send_resume(&self) {
+        (self.sender)(Message::Resume);
+    }
+
+    /// Exits the main loop after the current action, restoring the terminal.
+    pub fn quit(&self) {
+        (self.sender)(Message::Quit);
+    }
+
+    /// Restores the terminal and stops the process, like Ctrl-Z does in a shell. The view is
+    /// redrawn once the process is continued.
+    ///
+    /// The terminal is in raw mode, so Ctrl-Z arrives as the key `'\u{1a}'` rather than as a
+    /// signal. Applications wanting the usual behavior call this when receiving it.
+    pub fn suspend(&self) {
+        (self.sender)(Message::Suspend);
+    }
+
+    /// Runs `f` on the main loop with the terminal restored to its original state, then
+    /// delivers the action it returns. No input is read and nothing is drawn until `f` returns.
+    pub fn release_terminal<F>(&self, f: F)
+        where F: FnOnce() -> A + Send + 'static
+    {
+        (self.sender)(Message::Release(Box::new(f)));
+    }
+
+    /// Runs `command` in the released terminal, e.g. to open an editor, and delivers the
+    /// action created from its exit status once it finished.
+    pub fn run_command<F>(&self, mut command: Command, on_exit: F)
+        where F: FnOnce(io::Result<ExitStatus>) -> A + Send + 'static
+    {
+        self.release_terminal(move || on_exit(command.status()))
+    }
+
+    fn add_timer(&self, schedule: Schedule, make_action: ActionFn<A>) -> TimerId {
+        let id = TimerId(self.next_id.fetch_add(1, Ordering::Relaxed));
+
+        // if the loop is gone, the timer would never fire anyway
+        (self.sender)(Message::AddTimer(id, schedule, make_action));
+        id
+    }
+
+    /// Delivers `action` once after `delay`.
+    pub fn after(&self, delay: Duration, action: A) -> TimerId {
+        let mut action = Some(action);
+        self.add_timer(Schedule::Once(delay),
+                       Box::new(move || action.take().expect("one-shot timer fired twice")))
+    }
+
+    /// Delivers an action created by `make_action` every `interval`, until cancelled.
+    pub fn every<F>(&self, interval: Duration, make_action: F) -> TimerId
+        where F: FnMut() -> A + Send + 'static
+    {
+        self.add_timer(Schedule::Every(interval), Box::new(make_action))
+    }
+
+    /// Delivers an action created by `make_action` once per frame, until cancelled.
+    ///
+    /// Frames are paced by `MainLoop::min_delay` (or `max_fps`); an unlimited loop ticks at
+    /// 60 frames per second.
+    pub fn on_frame<F>(&self, make_action: F) -> TimerId
+        where F: FnMut() -> A + Send + 'static
+    {
+        self.add_timer(Schedule::Frame, Box::new(make_action))
+    }
+
+    /// Asks the main loop to redraw the view. Multiple requests before the next frame are
+    /// coalesced into a single redraw.
+    pub fn request_redraw(&self) {
+        (self.sender)(Message::Redraw);
+    }
+
+    /// Stops a timer. Cancelling a timer that already fired has no effect.
+    pub fn cancel(&self, id: TimerId) {
+        (self.sender)(Message::CancelTimer(id));
+    }
+
+    /// Runs `task` on a worker thread and delivers the action it returns to the application.
+    ///
+    /// At most `MainLoop::max_tasks` tasks run at the same time, others are queued. The task
+    /// receives its own `TaskHandle` to check for cancellation.
+    pub fn spawn<F>(&self, task: F) -> TaskHandle
+        where F: FnOnce(&TaskHandle) -> A + Send + 'static
+    {
+        let handle = TaskHandle::new();
+        let task_handle = handle.clone();
+        let sender = self.sender.clone();
+
+        self.executor.submit(Box::new(move || {
+            if task_handle.is_cancelled() {
+                return;
+            }
+
+            let action = task(&task_handle);
+
+            if !task_handle.is_cancelled() {
+                sender(Message::Action(action));
+            }
+        }));
+
+        handle
+    }
+
+    /// A handle for a part of the application with actions of its own, e.g. a `Component`.
+    /// Actions sent through it, or by its timers and tasks, are converted by `f` and delivered
+    /// to the application like those sent through this handle.
+    pub fn map<B, F>(&self, f: F) -> LoopHandle<B>
+        where B: Send + 'static,
+              F: Fn(B) -> A + Send + Sync + 'static
+    {
+        let f = sync::Arc::new(f);
+        let sender = self.sender.clone();
+
+        LoopHandle {
+            sender: sync::Arc::new(move |msg: Message<B>| sender(msg.map(&f))),
+            next_id: self.next_id.clone(),
+            executor: self.executor.clone(),
+        }
+    }
+}
@@ -0,0 +1,439 @@
+use libc;
+use rustty::Event;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::{mem, ptr, str};
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+
+/// Signals reported through the event source.
+const SIGNALS: [libc::c_int; 6] = [libc::SIGWINCH,
+                                   libc::SIGTSTP,
+                                   libc::SIGCONT,
+                                   libc::SIGTERM,
+                                   libc::SIGINT,
+                                   libc::SIGHUP];
+
+/// Write end of the pipe receiving signal numbers, or -1 if no event source is active.
+static SIGNAL_PIPE: AtomicI32 = AtomicI32::new(-1);
+
+/// SIGWINCH handler installed before ours, usually the one of `rustty::Terminal`, and the
+/// flags it was installed with.
+static PREV_SIGWINCH: AtomicUsize = AtomicUsize::new(0);
+static PREV_SIGWINCH_FLAGS: AtomicI32 = AtomicI32::new(0);
+
+type SigAction = extern "C" fn(libc::c_int, *mut libc::siginfo_t, *mut libc::c_void);
+type SigHandler = extern "C" fn(libc::c_int);
+
+extern "C" fn signal_handler(sig: libc::c_int,
+                             info: *mut libc::siginfo_t,
+                             context: *mut libc::c_void) {
+    // the interrupted code may be about to look at errno
+    let errno = unsafe { *libc::__errno_location() };
+
+    let fd = SIGNAL_PIPE.load(Ordering::SeqCst);
+    if fd >= 0 {
+        let buf = [sig as u8];
+        unsafe {
+            libc::write(fd, buf.as_ptr() as *const libc::c_void, 1);
+        }
+    }
+
+    // rustty relies on its own handler to notice the resize, so keep calling it
+    if sig == libc::SIGWINCH {
+        let prev = PREV_SIGWINCH.load(Ordering::SeqCst);
+        let flags = PREV_SIGWINCH_FLAGS.load(Ordering::SeqCst);
+        if prev != libc::SIG_DFL && prev != libc::SIG_IGN {
+            if flags & libc::SA_SIGINFO != 0 {
+                let prev: SigAction = unsafe { mem::transmute(prev) };
+                prev(sig, info, context);
+            } else {
+                let prev: SigHandler = unsafe { mem::transmute(prev) };
+                prev(sig);
+            }
+        }
+    }
+
+    unsafe {
+        *libc::__errno_location() = errno;
+    }
+}
+
+/// Installs our handler for `sig`, returning the previous action.
+fn install_handler(sig: libc::c_int) -> io::Result<libc::sigaction> {
+    let mut action: libc::sigaction = unsafe { mem::zeroed() };
+    let mut prev_action: libc::sigaction = unsafe { mem::zeroed() };
+    action.sa_sigaction = signal_handler as SigAction as usize;
+    action.sa_flags = libc::SA_SIGINFO;
+
+    if unsafe { libc::sigaction(sig, ptr::null(), &mut prev_action) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    if sig == libc::SIGWINCH {
+        PREV_SIGWINCH_FLAGS.store(prev_action.sa_flags, Ordering::SeqCst);
+        PREV_SIGWINCH.store(prev_action.sa_sigaction, Ordering::SeqCst);
+    }
+
+    if unsafe { libc::sigaction(sig, &action, ptr::null_mut()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(prev_action)
+}
+
+/// Installs our SIGWINCH handler again after a new `rustty::Terminal` replaced it.
+pub(crate) fn rechain_sigwinch() {
+    if SIGNAL_PIPE.load(Ordering::SeqCst) < 0 {
+        return;
+    }
+
+    let mut current: libc::sigaction = unsafe { mem::zeroed() };
+    unsafe {
+        libc::sigaction(libc::SIGWINCH, ptr::null(), &mut current);
+    }
+    if current.sa_sigaction != signal_handler as SigAction as usize {
+        let _ = install_handler(libc::SIGWINCH);
+    }
+}
+
+/// Stops the process group until it is continued, as the default action of SIGTSTP would.
+pub(crate) fn stop_process() {
+    unsafe {
+        let mut default: libc::sigaction = mem::zeroed();
+        let mut tstp: libc::sigaction = mem::zeroed();
+        let mut cont: libc::sigaction = mem::zeroed();
+        default.sa_sigaction = libc::SIG_DFL;
+
+        // continuing is expected here, it must not be reported as a resume from outside
+        libc::sigaction(libc::SIGTSTP, &default, &mut tstp);
+        libc::sigaction(libc::SIGCONT, &default, &mut cont);
+
+        libc::kill(0, libc::SIGTSTP);
+
+        // execution continues here after SIGCONT
+        libc::sigaction(libc::SIGTSTP, &tstp, ptr::null_mut());
+        libc::sigaction(libc::SIGCONT, &cont, ptr::null_mut());
+    }
+}
+
+/// Input received from the terminal.
+pub(crate) enum Input {
+    Event(Event),
+    /// The terminal window changed its size.
+    Resize,
+    /// The process was asked to stop, e.g. by SIGTSTP.
+    Suspend,
+    /// The process was continued after being stopped from outside.
+    Resume,
+    /// The process was asked to terminate.
+    Quit,
+}
+
+/// Both ends of a non-blocking pipe, used to interrupt a blocking `poll`.
+struct Pipe {
+    read: RawFd,
+    write: RawFd,
+}
+
+impl Pipe {
+    fn new() -> io::Result<Pipe> {
+        let mut fds = [0 as libc::c_int; 2];
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        for &fd in &fds {
+            unsafe {
+                let flags = libc::fcntl(fd, libc::F_GETFL);
+                libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK);
+                libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+            }
+        }
+
+        Ok(Pipe {
+            read: fds[0],
+            write: fds[1],
+        })
+    }
+
+    /// Discards everything written to the pipe so far.
+    fn drain(&self) {
+        let mut buf = [0u8; 64];
+        loop {
+            let n = unsafe { libc::read(self.read, buf.as_mut_ptr() as *mut libc::c_void, 64) };
+            if n <= 0 {
+                break;
+            }
+        }
+    }
+}
+
+impl Drop for Pipe {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.read);
+            libc::close(self.write);
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Running,
+    Paused,
+    Stopped,
+}
+
+struct Control {
+    pipe: Pipe,
+    // requested state, and whether the reader is currently paused or no longer running
+    state: Mutex<(State, bool)>,
+    cvar: Condvar,
+}
+
+/// Controls an `EventSource` running on another thread.
+#[derive(Clone)]
+pub(crate) struct InputHandle {
+    control: Arc<Control>,
+}
+
+impl InputHandle {
+    fn request(&self, state: State) {
+        self.control.state.lock().unwrap().0 = state;
+
+        let buf = [0u8];
+        // if the pipe is full, a wakeup is already pending
+        unsafe {
+            libc::write(self.control.pipe.write, buf.as_ptr() as *const libc::c_void, 1);
+        }
+    }
+
+    /// Stops reading input, returning once the terminal is no longer read from. Signals
+    /// arriving until `resume` is called are ignored.
+    pub fn pause(&self) {
+        self.request(State::Paused);
+
+        let mut state = self.control.state.lock().unwrap();
+        while state.0 == State::Paused && !state.1 {
+            state = self.control.cvar.wait(state).unwrap();
+        }
+    }
+
+    pub fn resume(&self) {
+        self.control.state.lock().unwrap().0 = State::Running;
+        self.control.cvar.notify_all();
+    }
+
+    /// Causes `EventSource::run` to return.
+    pub fn stop(&self) {
+        self.request(State::Stopped);
+        self.control.cvar.notify_all();
+    }
+}
+
+/// Reads key events from the terminal.
+///
+/// Input is read through a descriptor of its own, so waiting for events never blocks drawing
+/// on the `rustty::Terminal`. Raw mode is a property of the terminal device, so the settings
+/// made by rustty apply here as well.
+///
+/// Signals relevant to the application, like SIGWINCH or SIGTSTP, are reported as input too.
+pub(crate) struct EventSource {
+    tty: File,
+    control: Arc<Control>,
+    signals: Pipe,
+    prev_actions: Vec<(libc::c_int, libc::sigaction)>,
+
+    // trailing bytes of an incomplete UTF-8 sequence
+    pending: Vec<u8>,
+}
+
+impl EventSource {
+    pub fn new() -> io::Result<EventSource> {
+        let tty = OpenOptions::new().read(true).open("/dev/tty")?;
+        let control = Arc::new(Control {
+            pipe: Pipe::new()?,
+            state: Mutex::new((State::Running, false)),
+            cvar: Condvar::new(),
+        });
+        let signals = Pipe::new()?;
+
+        // install the signal handlers, remembering the previous ones to restore them later
+        SIGNAL_PIPE.store(signals.write, Ordering::SeqCst);
+        let mut prev_actions = Vec::new();
+        for &sig in &SIGNALS {
+            match install_handler(sig) {
+                Ok(prev) => prev_actions.push((sig, prev)),
+                Err(err) => {
+                    restore_handlers(&prev_actions);
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(EventSource {
+            tty,
+            control,
+            signals,
+            prev_actions,
+            pending: Vec::new(),
+        })
+    }
+
+    pub fn handle(&self) -> InputHandle {
+        InputHandle { control: self.control.clone() }
+    }
+
+    /// Blocks reading input, passing it to `f`, until stopped through an `InputHandle`. Fails
+    /// when the terminal can no longer be read, e.g. after it hung up.
+    pub fn run<F: FnMut(Input)>(&mut self, f: F) -> io::Result<()> {
+        let res = self.read_input(f);
+
+        // nothing is read anymore, so pausing must not wait for the reader
+        let mut state = self.control.state.lock().unwrap();
+        state.1 = true;
+        self.control.cvar.notify_all();
+
+        res
+    }
+
+    fn read_input<F: FnMut(Input)>(&mut self, mut f: F) -> io::Result<()> {
+        loop {
+            let mut fds = [libc::pollfd {
+                               fd: self.tty.as_raw_fd(),
+                               events: libc::POLLIN,
+                               revents: 0,
+                           },
+                           libc::pollfd {
+                               fd: self.control.pipe.read,
+                               events: libc::POLLIN,
+                               revents: 0,
+                           },
+                           libc::pollfd {
+                               fd: self.signals.read,
+                               events: libc::POLLIN,
+                               revents: 0,
+                           }];
+
+            let res = unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, -1) };
+
+            if res < 0 {
+                let err = io::Error::last_os_error();
+                // interrupted by a signal, e.g. SIGWINCH
+                if err.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(err);
+            }
+
+            if fds[1].revents != 0 {
+                self.control.pipe.drain();
+                if !self.wait_running() {
+                    return Ok(());
+                }
+                continue;
+            }
+
+            if fds[2].revents != 0 {
+                self.read_signals(&mut f);
+            }
+
+            if fds[0].revents & libc::POLLIN != 0 {
+                self.read_events(&mut f)?;
+            } else if fds[0].revents != 0 {
+                // terminal hung up or is otherwise unusable
+                return Err(io::Error::new(io::ErrorKind::BrokenPipe, "terminal closed"));
+            }
+        }
+    }
+
+    /// Blocks while paused. Returns `false` if the event source was stopped.
+    fn wait_running(&mut self) -> bool {
+        let control = self.control.clone();
+        let mut state = control.state.lock().unwrap();
+
+        if state.0 == State::Paused {
+            state.1 = true;
+            control.cvar.notify_all();
+
+            while state.0 == State::Paused {
+                state = control.cvar.wait(state).unwrap();
+            }
+            state.1 = false;
+
+            // whoever paused the input took care of the terminal in the meantime
+            self.signals.drain();
+        }
+
+        state.0 != State::Stopped
+    }
+
+    fn read_signals<F: FnMut(Input)>(&mut self, f: &mut F) {
+        let mut buf = [0u8; 64];
+        let n = unsafe {
+            libc::read(self.signals.read, buf.as_mut_ptr() as *mut libc::c_void, buf.len())
+        };
+        if n <= 0 {
+            return;
+        }
+
+        let received = &buf[..n as usize];
+        // several signals of a kind in a row are reported once
+        for &sig in &SIGNALS {
+            if !received.contains(&(sig as u8)) {
+                continue;
+            }
+
+            f(match sig {
+                libc::SIGWINCH => Input::Resize,
+                libc::SIGTSTP => Input::Suspend,
+                libc::SIGCONT => Input::Resume,
+                _ => Input::Quit,
+            });
+        }
+    }
+
+    fn read_events<F: FnMut(Input)>(&mut self, f: &mut F) -> io::Result<()> {
+        let mut buf = [0u8; 1024];
+        let n = self.tty.read(&mut buf)?;
+        if n == 0 {
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "terminal closed"));
+        }
+        self.pending.extend_from_slice(&buf[..n]);
+
+        let valid = match str::from_utf8(&self.pending) {
+            Ok(s) => s.len(),
+            Err(e) => {
+                match e.error_len() {
+                    // incomplete sequence at the end, wait for more input
+                    None => e.valid_up_to(),
+                    // garbage; skip it rather than stalling forever
+                    Some(len) => e.valid_up_to() + len,
+                }
+            }
+        };
+
+        for ch in String::from_utf8_lossy(&self.pending[..valid]).chars() {
+            f(Input::Event(Event::Key(ch)));
+        }
+        self.pending.drain(..valid);
+
+        Ok(())
+    }
+}
+
+fn restore_handlers(actions: &[(libc::c_int, libc::sigaction)]) {
+    for &(sig, ref action) in actions {
+        unsafe {
+            libc::sigaction(sig, action, ptr::null_mut());
+        }
+    }
+}
+
+impl Drop for EventSource {
+    fn drop(&mut self) {
+        restore_handlers(&self.prev_actions);
+        SIGNAL_PIPE.store(-1, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,552 @@
+//! Declarative key bindings.
+//!
+//! A `Keymap` binds key sequences to actions. Keymaps are layered, e.g. one per mode or per
+//! focused widget on top of a global one, and a `KeyDispatcher` resolves incoming keys against
+//! such a stack of layers.
+//!
+//! Keys are written in a notation similar to the one of Emacs and Vim:
+//!
+//! * a single character stands for itself, e.g. `q` or `?`
+//! * `C-x` is Ctrl and `M-x` is Alt (or Meta), which terminals send as Escape followed by `x`
+//! * `<Esc>`, `<Tab>`, `<Enter>`, `<Space>`, `<BS>` and `<lt>` (for `<`) name special keys
+//! * `<U+00E4>` is a key given by its code point, used for keys outside of ASCII in labels
+//! * keys separated by spaces form a sequence, e.g. `g g` or `C-x C-s`
+//!
+//! Since Alt chords begin with Escape, a binding for `<Esc>` alone is ambiguous with all of
+//! them, just like `g` is with `g g`. Ambiguous sequences are resolved by the next key or by
+//! `KeyDispatcher::flush`, typically called from a timer.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+const ESC: char = '\x1b';
+const DEL: char = '\x7f';
+
+/// A sequence of keys, as read from the terminal.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct KeySeq(Vec<char>);
+
+impl KeySeq {
+    #[inline]
+    pub fn new(keys: Vec<char>) -> KeySeq {
+        KeySeq(keys)
+    }
+
+    #[inline]
+    pub fn keys(&self) -> &[char] {
+        &self.0
+    }
+
+    /// Returns whether `self` is a proper prefix of `other`.
+    #[inline]
+    pub fn is_prefix_of(&self, other: &KeySeq) -> bool {
+        other.0.len() > self.0.len() && other.0.starts_with(&self.0)
+    }
+
+    /// Returns whether one of both sequences starts with the other.
+    #[inline]
+    fn overlaps(&self, other: &KeySeq) -> bool {
+        self == other || self.is_prefix_of(other) || other.is_prefix_of(self)
+    }
+}
+
+/// Error returned when parsing an invalid key notation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseKeysError {
+    token: String,
+}
+
+impl fmt::Display for ParseKeysError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid key: {:?}", self.token)
+    }
+}
+
+impl Error for ParseKeysError {}
+
+fn named_key(name: &str) -> Option<char> {
+    Some(match name.to_ascii_lowercase().as_str() {
+        "esc" => ESC,
+        "tab" => '\t',
+        "enter" | "cr" | "ret" => '\r',
+        "space" => ' ',
+        "bs" | "backspace" => DEL,
+        "lt" => '<',
+        _ if name.len() > 2 && name.get(..2).is_some_and(|p| p.eq_ignore_ascii_case("u+")) => {
+            return u32::from_str_radix(&name[2..], 16).ok().and_then(::std::char::from_u32)
+        }
+        _ => return None,
+    })
+}
+
+fn ctrl(ch: char) -> Option<char> {
+    match ch {
+        'a'..='z' => Some((ch as u8 - b'a' + 1) as char),
+        '@'..='_' => Some((ch as u8 - b'@') as char),
+        '?' => Some(DEL),
+        _ => None,
+    }
+}
+
+fn parse_token(token: &str, keys: &mut Vec<char>) -> Result<(), ParseKeysError> {
+    let err = || ParseKeysError { token: token.to_owned() };
+
+    let mut rest = token;
+    let (mut control, mut meta) = (false, false);
+    loop {
+        if rest.len() > 2 && rest.starts_with("C-") {
+            control = true;
+        } else if rest.len() > 2 && rest.starts_with("M-") {
+            meta = true;
+        } else {
+            break;
+        }
+        rest = &rest[2..];
+    }
+
+    let mut ch = if rest.len() > 2 && rest.starts_with('<') && rest.ends_with('>') {
+        named_key(&rest[1..rest.len() - 1]).ok_or_else(err)?
+    } else {
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) => ch,
+            _ => return Err(err()),
+        }
+    };
+
+    if control {
+        ch = ctrl(ch).ok_or_else(err)?;
+    }
+    if meta {
+        keys.push(ESC);
+    }
+    keys.push(ch);
+
+    Ok(())
+}
+
+impl FromStr for KeySeq {
+    type Err = ParseKeysError;
+
+    fn from_str(s: &str) -> Result<KeySeq, ParseKeysError> {
+        let mut keys = Vec::new();
+        for token in s.split_whitespace() {
+            parse_token(token, &mut keys)?;
+        }
+
+        if keys.is_empty() {
+            return Err(ParseKeysError { token: s.to_owned() });
+        }
+
+        Ok(KeySeq(keys))
+    }
+}
+
+fn fmt_key(ch: char, f: &mut fmt::Formatter) -> fmt::Result {
+    match ch {
+        ESC => f.write_str("<Esc>"),
+        '\t' => f.write_str("<Tab>"),
+        '\r' => f.write_str("<Enter>"),
+        ' ' => f.write_str("<Space>"),
+        DEL => f.write_str("<BS>"),
+        '<' => f.write_str("<lt>"),
+        '\x01'..='\x1a' => write!(f, "C-{}", (ch as u8 - 1 + b'a') as char),
+        '\x00'..='\x1f' => write!(f, "C-{}", (ch as u8 + b'@') as char),
+        // labels are drawn as ASCII
+        _ if !ch.is_ascii() => write!(f, "<U+{:04X}>", ch as u32),
+        _ => write!(f, "{}", ch),
+    }
+}
+
+impl fmt::Display for KeySeq {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut keys = self.0.iter().peekable();
+        let mut first = true;
+
+        while let Some(&ch) = keys.next() {
+            if !first {
+                f.write_str(" ")?;
+            }
+            first = false;
+
+            // Escape followed by another key is how Alt chords arrive
+            match keys.peek() {
+                Some(&&next) if ch == ESC && next != ESC => {
+                    f.write_str("M-")?;
+                    fmt_key(next, f)?;
+                    keys.next();
+                }
+                _ => fmt_key(ch, f)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A key sequence bound to an action.
+#[derive(Clone, Debug)]
+pub struct Binding<A> {
+    keys: KeySeq,
+    label: String,
+    action: A,
+    help: String,
+}
+
+impl<A> Binding<A> {
+    #[inline]
+    pub fn keys(&self) -> &KeySeq {
+        &self.keys
+    }
+
+    /// The key sequence in normalized notation, e.g. for display.
+    #[inline]
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    #[inline]
+    pub fn action(&self) -> &A {
+        &self.action
+    }
+
+    #[inline]
+    pub fn help(&self) -> &str {
+        &self.help
+    }
+}
+
+/// A named set of key bindings.
+#[derive(Clone, Debug)]
+pub struct Keymap<A> {
+    name: String,
+    bindings: Vec<Binding<A>>,
+}
+
+/// Result of looking up a key sequence.
+enum Lookup<'a, A: 'a> {
+    None,
+    /// Bound to an action; the flag tells whether longer bindings start with the sequence.
+    Exact(&'a A, bool),
+    /// Only longer bindings start with the sequence.
+    Prefix,
+}
+
+impl<A> Keymap<A> {
+    pub fn new(name: &str) -> Keymap<A> {
+        Keymap {
+            name: name.to_owned(),
+            bindings: Vec::new(),
+        }
+    }
+
+    /// Binds the key sequence written as `keys` to `action`.
+    ///
+    /// Panics if `keys` is not a valid key notation.
+    pub fn bind(mut self, keys: &str, action: A, help: &str) -> Keymap<A> {
+        let seq = keys.parse().unwrap_or_else(|e| panic!("{}", e));
+        self.insert(seq, action, help);
+        self
+    }
+
+    /// Adds a binding. Earlier bindings of the same sequence take precedence.
+    pub fn insert(&mut self, keys: KeySeq, action: A, help: &str) {
+        self.bindings.push(Binding {
+            label: keys.to_string(),
+            keys,
+            action,
+            help: help.to_owned(),
+        });
+    }
+
+    /// Removes all bindings of `keys`.
+    pub fn unbind(&mut self, keys: &KeySeq) {
+        self.bindings.retain(|b| b.keys != *keys);
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline]
+    pub fn bindings(&self) -> &[Binding<A>] {
+        &self.bindings
+    }
+
+    fn lookup(&self, keys: &KeySeq) -> Lookup<'_, A> {
+        let longer = self.bindings.iter().any(|b| keys.is_prefix_of(&b.keys));
+
+        match self.bindings.iter().find(|b| b.keys == *keys) {
+            Some(b) => Lookup::Exact(&b.action, longer),
+            None if longer => Lookup::Prefix,
+            None => Lookup::None,
+        }
+    }
+}
+
+impl<A: PartialEq> Keymap<A> {
+    /// Returns the keys bound to `action` in normalized notation, e.g. to label a button.
+    pub fn keys_for(&self, action: &A) -> Option<&str> {
+        self.bindings
+            .iter()
+            .find(|b| b.action == *action)
+            .map(|b| b.label())
+    }
+}
+
+/// Looks up `keys` in a stack of layers, the first one taking precedence. The first layer
+/// knowing the sequence at all decides, so layers shadow prefixes of lower ones as well.
+fn lookup<'a, A>(layers: &[&'a Keymap<A>], keys: &KeySeq) -> Lookup<'a, A> {
+    for layer in layers {
+        match layer.lookup(keys) {
+            Lookup::None => (),
+            found => return found,
+        }
+    }
+    Lookup::None
+}
+
+/// A key resolved by a `KeyDispatcher`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Resolved<A> {
+    Action(A),
+    /// A key not bound in any layer, e.g. to be used as text input.
+    Unbound(char),
+}
+
+/// Resolves keys against layered keymaps, keeping track of partially entered sequences.
+#[derive(Clone, Debug, Default)]
+pub struct KeyDispatcher {
+    pending: KeySeq,
+}
+
+impl KeyDispatcher {
+    pub fn new() -> KeyDispatcher {
+        KeyDispatcher::default()
+    }
+
+    /// The keys of a partially entered sequence.
+    #[inline]
+    pub fn pending(&self) -> &KeySeq {
+        &self.pending
+    }
+
+    #[inline]
+    pub fn is_pending(&self) -> bool {
+        !self.pending.0.is_empty()
+    }
+
+    /// Discards a partially entered sequence.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.pending.0.clear();
+    }
+
+    /// Processes `key`, returning the resolved actions and unbound keys in order. Nothing is
+    /// returned while a sequence is incomplete or ambiguous.
+    pub fn feed<A: Clone>(&mut self, layers: &[&Keymap<A>], key: char) -> Vec<Resolved<A>> {
+        self.pending.0.push(key);
+        self.resolve(layers, false)
+    }
+
+    /// Resolves a pending sequence without waiting for further keys. An ambiguous sequence
+    /// resolves to its shorter binding.
+    pub fn flush<A: Clone>(&mut self, layers: &[&Keymap<A>]) -> Vec<Resolved<A>> {
+        self.resolve(layers, true)
+    }
+
+    fn resolve<A: Clone>(&mut self, layers: &[&Keymap<A>], flush: bool) -> Vec<Resolved<A>> {
+        let mut resolved = Vec::new();
+
+        while self.is_pending() {
+            match lookup(layers, &self.pending) {
+                Lookup::Exact(action, longer) if flush || !longer => {
+                    resolved.push(Resolved::Action(action.clone()));
+                    self.reset();
+                }
+                Lookup::Exact(..) | Lookup::Prefix if !flush => break,
+                _ => {
+                    // the sequence cannot be completed anymore. resolve its longest bound
+                    // prefix, or pass on its first key, then retry with the remaining keys
+                    let keys = &self.pending.0;
+                    let bound = (1..keys.len()).rev().filter_map(|n| {
+                        match lookup(layers, &KeySeq(keys[..n].to_vec())) {
+                            Lookup::Exact(action, _) => Some((n, action)),
+                            _ => None,
+                        }
+                    }).next();
+
+                    match bound {
+                        Some((n, action)) => {
+                            resolved.push(Resolved::Action(action.clone()));
+                            self.pending.0.drain(..n);
+                        }
+                        None => {
+                            resolved.push(Resolved::Unbound(keys[0]));
+                            self.pending.0.remove(0);
+                        }
+                    }
+                }
+            }
+        }
+
+        resolved
+    }
+}
+
+/// A problem found in a stack of keymaps by `conflicts`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Conflict {
+    /// The sequence is bound more than once in the same layer.
+    Duplicate { layer: String, keys: KeySeq },
+    /// The sequence is bound and also starts a longer binding, so it only fires after the
+    /// next key or a timeout.
+    Prefix {
+        layer: String,
+        keys: KeySeq,
+        longer: KeySeq,
+    },
+    /// The binding is unreachable because a higher layer binds the same sequence, a prefix of
+    /// it or a longer sequence starting with it.
+    Shadowed {
+        layer: String,
+        keys: KeySeq,
+        by: String,
+    },
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Conflict::Duplicate { ref layer, ref keys } => {
+                write!(f, "{}: {} is bound more than once", layer, keys)
+            }
+            Conflict::Prefix { ref layer, ref keys, ref longer } => {
+                write!(f, "{}: {} is a prefix of {}", layer, keys, longer)
+            }
+            Conflict::Shadowed { ref layer, ref keys, ref by } => {
+                write!(f, "{}: {} is shadowed by {}", layer, keys, by)
+            }
+        }
+    }
+}
+
+/// Finds conflicting bindings in a stack of layers, the first one taking precedence.
+pub fn conflicts<A>(layers: &[&Keymap<A>]) -> Vec<Conflict> {
+    let mut found = Vec::new();
+
+    for (i, layer) in layers.iter().enumerate() {
+        for (j, b) in layer.bindings.iter().enumerate() {
+            let earlier = &layer.bindings[..j];
+
+            if earlier.iter().any(|e| e.keys == b.keys) {
+                // reported once, for the first repetition
+                if earlier.iter().filter(|e| e.keys == b.keys).count() == 1 {
+                    found.push(Conflict::Duplicate {
+                        layer: layer.name.clone(),
+                        keys: b.keys.clone(),
+                    });
+                }
+                continue;
+            }
+
+            if let Some(longer) = layer.bindings.iter().find(|o| b.keys.is_prefix_of(&o.keys)) {
+                found.push(Conflict::Prefix {
+                    layer: layer.name.clone(),
+                    keys: b.keys.clone(),
+                    longer: longer.keys.clone(),
+                });
+            }
+
+            let shadowing = layers[..i].iter().find(|upper| {
+                upper.bindings.iter().any(|u| u.keys.overlaps(&b.keys))
+            });
+            if let Some(upper) = shadowing {
+                found.push(Conflict::Shadowed {
+                    layer: layer.name.clone(),
+                    keys: b.keys.clone(),
+                    by: upper.name.clone(),
+                });
+            }
+        }
+    }
+
+    found
+}
+
+/// A line of a key binding overview, see `view::help::KeyHelp`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HelpEntry<'a> {
+    pub layer: &'a str,
+    pub keys: &'a str,
+    pub help: &'a str,
+}
+
+/// Lists the bindings active in a stack of layers, omitting unreachable ones and those
+/// without help text.
+pub fn help<'a, A>(layers: &[&'a Keymap<A>]) -> Vec<HelpEntry<'a>> {
+    let mut entries = Vec::new();
+
+    for (i, layer) in layers.iter().enumerate() {
+        for (j, b) in layer.bindings.iter().enumerate() {
+            if b.help.is_empty() || layer.bindings[..j].iter().any(|e| e.keys == b.keys) {
+                continue;
+            }
+
+            let shadowed = layers[..i].iter().any(|upper| {
+                upper.bindings.iter().any(|u| u.keys.overlaps(&b.keys))
+            });
+            if shadowed {
+                continue;
+            }
+
+            entries.push(HelpEntry {
+                layer: &layer.name,
+                keys: &b.label,
+                help: &b.help,
+            });
+        }
+    }
+
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KeySeq;
+
+    fn label(keys: &[char]) -> String {
+        KeySeq::new(keys.to_vec()).to_string()
+    }
+
+    #[test]
+    fn labels_parse_back() {
+        let mut keys: Vec<char> = (0u8..0x80).map(|b| b as char).collect();
+        keys.extend(&['ä', '€', '\u{1f600}', '\u{85}']);
+
+        for &ch in &keys {
+            for seq in &[vec![ch], vec!['\x1b', ch], vec!['g', ch]] {
+                let label = label(seq);
+                assert!(label.is_ascii(), "{:?}", label);
+                assert_eq!(label.parse::<KeySeq>().unwrap().keys(), &seq[..], "{}", label);
+            }
+        }
+    }
+
+    #[test]
+    fn non_ascii_names() {
+        assert!("<€x>".parse::<KeySeq>().is_err());
+        assert!("<ä>".parse::<KeySeq>().is_err());
+        assert!("<U+€>".parse::<KeySeq>().is_err());
+        assert_eq!("<u+20ac>".parse::<KeySeq>().unwrap().keys(), &['€']);
+    }
+
+    #[test]
+    fn control_labels() {
+        assert_eq!(label(&['\x01', '\x1a']), "C-a C-z");
+        assert_eq!(label(&['\x00', '\x1c', '\x1d', '\x1e', '\x1f']), "C-@ C-\\ C-] C-^ C-_");
+        assert_eq!(label(&['\x1b', 'ä']), "M-<U+00E4>");
+    }
+}
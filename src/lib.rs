@@ -1,9 +1,43 @@
 extern crate bresenham;
+extern crate libc;
 extern crate rustty;
+extern crate term;
 
-use std::{convert, thread, time, sync};
+use std::{cmp, convert, thread, time, sync};
 
+pub mod component;
+pub mod config;
+mod executor;
+mod frame;
+mod handle;
+mod input;
+pub mod keymap;
+pub mod pty;
+mod render;
+mod timer;
 pub mod view;
+pub mod vt;
+
+pub use executor::TaskHandle;
+pub use handle::LoopHandle;
+pub use timer::TimerId;
+
+use executor::Executor;
+use frame::FrameScheduler;
+use handle::Message;
+use input::{EventSource, Input, InputHandle};
+use render::Renderer;
+use timer::TimerQueue;
+use view::{Buffer, ColorSupport, Damage};
+
+/// Frame interval used for frame ticks if the main loop is not rate-limited.
+const DEFAULT_FRAME_DELAY_MS: u64 = 16;
+
+/// Default number of background tasks running concurrently.
+const DEFAULT_MAX_TASKS: usize = 4;
+
+/// How often the thread forwarding actions from `run_with_setup` checks for the loop to end.
+const FORWARD_POLL: time::Duration = time::Duration::from_millis(100);
 
 pub trait Application: Sized {
     type Action: convert::From<rustty::Event> + Send + 'static;
@@ -12,31 +46,141 @@ pub trait Application: Sized {
     fn handle_action(self, action: Self::Action) -> (Self, Option<Self::Task>);
     fn exec_task(&self, task: Self::Task) -> bool;
     fn view(&self, ctx: &mut view::DrawingContext);
+
+    /// Called when the terminal size changed, before redrawing the view.
+    fn handle_resize(self, _size: rustty::Size) -> (Self, Option<Self::Task>) {
+        (self, None)
+    }
+
+    /// Called once before the first draw. The handle can be stored to schedule timers later on.
+    fn init(&mut self, _handle: LoopHandle<Self::Action>) {}
+
+    /// Theme the view is drawn with. Defaults to the built-in theme.
+    fn theme(&self) -> Option<&view::theme::Theme> {
+        None
+    }
+
+    /// Store of the state widgets retain between frames. Without one, widgets start from their
+    /// default state every frame.
+    fn states(&self) -> Option<&view::state::StateStore> {
+        None
+    }
 }
 
-fn draw_view<T: Application>(term: sync::Arc<sync::Mutex<rustty::Terminal>>, app: &T) {
-    // unlock terminal
-    let mut t = term.lock().unwrap();
+/// Output state kept between frames.
+struct Screen {
+    term: rustty::Terminal,
+    buffer: Buffer,
+    damage: Damage,
+    renderer: Renderer,
+    colors: ColorSupport,
+}
 
-    {
-        let mut ctx = view::DrawingContext::new(&mut *t);
-        app.view(&mut ctx);
+impl Screen {
+    fn new(colors: ColorSupport) -> Screen {
+        let term = rustty::Terminal::new().unwrap();
+        input::rechain_sigwinch();
+        let size = (term.cols(), term.rows());
+        let mut screen = Screen {
+            term,
+            buffer: Buffer::new(size),
+            damage: Damage::new(),
+            renderer: Renderer::new(size, colors).unwrap(),
+            colors,
+        };
+
+        // send the setup sequences queued by rustty
+        screen.cleared();
+        screen
+    }
+
+    /// Restores the terminal while running `f`, then sets it up again. Input is not read in the
+    /// meantime.
+    fn release<R, F: FnOnce() -> R>(self, input: &InputHandle, f: F) -> (Screen, R) {
+        input.pause();
+        let colors = self.colors;
+        drop(self);
+
+        let result = f();
+
+        let screen = Screen::new(colors);
+        input.resume();
+        (screen, result)
     }
 
-    // FIXME: remove all unwraps
-    t.swap_buffers().unwrap();
+    #[inline]
+    fn size(&self) -> rustty::Size {
+        (self.term.cols(), self.term.rows())
+    }
+
+    /// Checks for a changed terminal size, returning the new one.
+    fn try_resize(&mut self) -> Option<rustty::Size> {
+        let size = self.term.try_resize().unwrap();
+        if size.is_some() {
+            self.cleared();
+        }
+        size
+    }
+
+    /// rustty cleared the screen, or is about to: let it flush its output and start over.
+    fn cleared(&mut self) {
+        // rustty's own buffers are never drawn into, so it only sends its pending control
+        // sequences
+        self.term.swap_buffers().unwrap();
+
+        let size = self.size();
+        self.buffer.resize(size);
+        self.damage.invalidate();
+        self.renderer.reset(size);
+    }
+
+    fn draw<T: Application>(&mut self, app: &T, elapsed: time::Duration) {
+        self.damage.begin_frame(self.buffer.size());
+        {
+            let mut ctx = view::DrawingContext::with_damage(&mut self.buffer, &mut self.damage);
+            ctx.set_elapsed(elapsed);
+            ctx.set_colors(self.colors);
+            if let Some(theme) = app.theme() {
+                ctx.set_theme(theme);
+            }
+            if let Some(store) = app.states() {
+                store.begin_frame();
+                ctx.set_store(store);
+            }
+            app.view(&mut ctx);
+        }
+        if let Some(store) = app.states() {
+            store.end_frame();
+        }
+
+        // FIXME: remove all unwraps
+        self.renderer.render(&self.buffer, &self.damage).unwrap();
+        self.damage.end_frame();
+    }
 }
 
 pub struct MainLoop {
     min_delay: Option<time::Duration>,
-    last_draw: time::Instant,
+    redraw_on_action: bool,
+    max_tasks: usize,
+    colors: Option<ColorSupport>,
+    started: time::Instant,
+}
+
+impl Default for MainLoop {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl MainLoop {
     pub fn new() -> MainLoop {
         MainLoop {
             min_delay: None,
-            last_draw: time::Instant::now(),
+            redraw_on_action: true,
+            max_tasks: DEFAULT_MAX_TASKS,
+            colors: None,
+            started: time::Instant::now(),
         }
     }
 
@@ -55,75 +199,223 @@ impl MainLoop {
         self
     }
 
+    /// Only redraw when requested through `LoopHandle::request_redraw`, instead of after every
+    /// action. Useful for applications that receive many actions not affecting the view.
+    pub fn redraw_on_request(&mut self) -> &mut MainLoop {
+        self.redraw_on_action = false;
+        self
+    }
+
+    /// Limits the number of tasks started through `LoopHandle::spawn` running at the same time.
+    pub fn max_tasks(&mut self, max_tasks: usize) -> &mut MainLoop {
+        self.max_tasks = max_tasks;
+        self
+    }
+
+    /// Draws with the given colors instead of those detected through
+    /// `ColorSupport::detect`. Colors beyond them are replaced by the nearest available ones.
+    pub fn colors(&mut self, colors: ColorSupport) -> &mut MainLoop {
+        self.colors = Some(colors);
+        self
+    }
+
     pub fn run<T: Application>(&mut self, app: T) {
-        self.run_with_setup(app, |_| {});
+        self.run_with_handle(app, |_| {});
     }
 
-    pub fn run_with_setup<T: Application, F>(&mut self, mut app: T, setup: F)
-        where F: FnOnce(sync::mpsc::Sender<<T as Application>::Action>) -> ()
+    /// Runs `app`, passing a `Sender` of actions to `setup` first, e.g. for threads delivering
+    /// actions from outside.
+    ///
+    /// Actions sent are forwarded to the main loop by a thread of its own, which ends with the
+    /// main loop. Sending fails from then on. `run_with_handle` passes a `LoopHandle` instead.
+    pub fn run_with_setup<T: Application, F>(&mut self, app: T, setup: F)
+        where F: FnOnce(sync::mpsc::Sender<<T as Application>::Action>)
     {
-        // unfortunately, the rustty API is pretty bad here; we cannot draw on the
-        // terminal while receiving events from it. for this reason, we need to
-        // lock and poll ...
-        let term = sync::Arc::new(sync::Mutex::new(rustty::Terminal::new().unwrap()));
+        self.run_with_handle(app, |handle| {
+            let (sender, receiver) = sync::mpsc::channel();
+            thread::spawn(move || {
+                loop {
+                    match receiver.recv_timeout(FORWARD_POLL) {
+                        Ok(action) => {
+                            if !handle.send(action) {
+                                break;
+                            }
+                        }
+                        Err(sync::mpsc::RecvTimeoutError::Timeout) => {
+                            if handle.is_closed() {
+                                break;
+                            }
+                        }
+                        Err(sync::mpsc::RecvTimeoutError::Disconnected) => break,
+                    }
+                }
+            });
+            setup(sender);
+        })
+    }
 
-        let (action_send, action_recv) = sync::mpsc::channel();
-        let running = sync::Arc::new(sync::atomic::AtomicBool::new(true));
+    /// Runs `app`, passing a handle to the main loop to `setup` first.
+    pub fn run_with_handle<T: Application, F>(&mut self, mut app: T, setup: F)
+        where F: FnOnce(LoopHandle<<T as Application>::Action>)
+    {
+        // the terminal is only used for output; input is read by the event source
+        // through a separate descriptor, so drawing never waits for it
+        let colors = self.colors.unwrap_or_else(ColorSupport::detect);
+        let mut screen = Screen::new(colors);
+        let mut events = EventSource::new().unwrap();
+        let reader = events.handle();
+
+        let (msg_send, msg_recv) = sync::mpsc::channel();
+        let executor = Executor::new(self.max_tasks);
+        let handle = LoopHandle::new(msg_send, executor.clone());
+
+        let frame_delay = self.min_delay
+            .unwrap_or_else(|| time::Duration::from_millis(DEFAULT_FRAME_DELAY_MS));
+        let mut timers = TimerQueue::new();
+        let mut frames = FrameScheduler::new(self.min_delay);
 
         // run setup function
-        setup(action_send.clone());
+        setup(handle.clone());
+        app.init(handle.clone());
 
         // start background thread that turns events into actions
-        let bg_term = term.clone();
-        let bg_running = running.clone();
+        let bg_handle = handle;
         let bg_thread = thread::spawn(move || {
-            loop {
-                // check if app is alive
-                if !bg_running.load(sync::atomic::Ordering::Relaxed) {
-                    break;
+            let res = events.run(|input| match input {
+                Input::Event(ev) => {
+                    let act: <T as Application>::Action = ev.into();
+                    bg_handle.send(act);
                 }
+                Input::Resize => bg_handle.send_resize(),
+                Input::Suspend => bg_handle.suspend(),
+                Input::Resume => bg_handle.send_resume(),
+                Input::Quit => bg_handle.quit(),
+            });
 
-                let mut t = bg_term.lock().unwrap();
-                match t.get_event(time::Duration::from_millis(10)).unwrap() {
-                    None => continue,
-                    Some(ev) => {
-                        let act: <T as Application>::Action = ev.into();
-                        action_send.send(act).unwrap();
-                    }
-                }
+            // the terminal is gone, e.g. closed along with its window, so there is no way to
+            // interact with the application anymore
+            if res.is_err() {
+                bg_handle.quit();
             }
         });
 
         // draw once initially
-        draw_view(term.clone(), &app);
+        self.started = time::Instant::now();
+        screen.draw(&app, time::Duration::from_secs(0));
+        frames.drawn(self.started);
+
+        'main: loop {
+            // wait for the next message, but no longer than until the next timer or deferred
+            // redraw is due
+            let deadline = match (timers.next_deadline(), frames.next_frame()) {
+                (Some(t), Some(f)) => Some(cmp::min(t, f)),
+                (t, f) => t.or(f),
+            };
+
+            let msg = match deadline {
+                None => {
+                    match msg_recv.recv() {
+                        Ok(msg) => Some(msg),
+                        Err(_) => break,
+                    }
+                }
+                Some(deadline) => {
+                    let now = time::Instant::now();
+                    if deadline <= now {
+                        None
+                    } else {
+                        match msg_recv.recv_timeout(deadline - now) {
+                            Ok(msg) => Some(msg),
+                            Err(sync::mpsc::RecvTimeoutError::Timeout) => None,
+                            Err(sync::mpsc::RecvTimeoutError::Disconnected) => break,
+                        }
+                    }
+                }
+            };
 
-        for action in action_recv.iter() {
-            // update state according to action
-            let (napp, ntask) = app.handle_action(action);
-            app = napp;
+            let mut actions = Vec::new();
+            let mut resized = None;
+            let mut released = None;
+            match msg {
+                Some(Message::Action(action)) => actions.push(action),
+                Some(Message::AddTimer(id, schedule, make_action)) => {
+                    timers.insert(id, schedule, frame_delay, make_action)
+                }
+                Some(Message::CancelTimer(id)) => timers.cancel(id),
+                Some(Message::Redraw) => frames.request(),
+                Some(Message::Resize) => resized = screen.try_resize(),
+                Some(Message::Quit) => break 'main,
+                Some(Message::Suspend) => {
+                    released = Some(screen.size());
+                    screen = screen.release(&reader, input::stop_process).0;
+                }
+                Some(Message::Resume) => {
+                    // stopped from outside, the shell has reset the terminal in the meantime
+                    released = Some(screen.size());
+                    screen = screen.release(&reader, || ()).0;
+                }
+                Some(Message::Release(f)) => {
+                    released = Some(screen.size());
+                    let (nscreen, action) = screen.release(&reader, f);
+                    screen = nscreen;
+                    actions.push(action);
+                }
+                None => (),
+            }
 
-            if let Some(task) = ntask {
-                if app.exec_task(task) {
-                    break;
+            if let Some(size) = released {
+                // the screen was set up from scratch, possibly with a different size
+                if screen.size() != size {
+                    resized = Some(screen.size());
                 }
+                frames.request();
             }
 
-            let now = time::Instant::now();
+            if let Some(size) = resized {
+                // resizing cleared the screen, the next frame redraws everything
+                let (napp, ntask) = app.handle_resize(size);
+                app = napp;
+                frames.request();
 
-            if let Some(min_delay) = self.min_delay {
-                if now - self.last_draw < min_delay {
-                    // do not redraw, updating too fast
-                    continue;
+                if let Some(task) = ntask {
+                    if app.exec_task(task) {
+                        break 'main;
+                    }
                 }
             }
+            actions.extend(timers.expire(time::Instant::now()));
+
+            for action in actions {
+                // update state according to action
+                let (napp, ntask) = app.handle_action(action);
+                app = napp;
 
-            // redraw
-            self.last_draw = now;
-            draw_view(term.clone(), &app);
+                if self.redraw_on_action {
+                    frames.request();
+                }
+
+                if let Some(task) = ntask {
+                    if app.exec_task(task) {
+                        break 'main;
+                    }
+                }
+            }
+
+            // redraw, unless updating too fast. in that case, the redraw stays pending and
+            // happens once the delay has elapsed
+            let now = time::Instant::now();
+            if frames.is_due(now) {
+                screen.draw(&app, now - self.started);
+                frames.drawn(now);
+                timers.align_frames(now + frame_delay);
+            }
         }
 
+        // running tasks are not waited for, their results are discarded
+        executor.shutdown();
+
         // shutdown background thread for clean terminal restoration
-        running.store(false, sync::atomic::Ordering::Relaxed);
+        reader.stop();
         bg_thread.join().unwrap();
     }
 }
@@ -0,0 +1,193 @@
+//! Programs running in a pseudo terminal, to be shown with `view::terminal::TerminalView`.
+
+use libc;
+use rustty::Size;
+use std::cmp::max;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::os::unix::process::CommandExt;
+use std::process::{Command, ExitStatus, Stdio};
+use std::ptr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use vt::Emulator;
+use LoopHandle;
+
+/// Terminal type announced to programs, unless the command sets another one.
+const TERM: &str = "xterm-256color";
+
+#[inline]
+fn winsize(size: Size) -> libc::winsize {
+    libc::winsize {
+        ws_col: size.0 as libc::c_ushort,
+        ws_row: size.1 as libc::c_ushort,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    }
+}
+
+#[inline]
+fn check(ret: libc::c_int) -> io::Result<libc::c_int> {
+    if ret < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(ret)
+    }
+}
+
+/// Opens a pseudo terminal, returning the controlling and the program side.
+fn open(size: Size) -> io::Result<(File, File)> {
+    let (mut master, mut slave): (RawFd, RawFd) = (-1, -1);
+    let ws = winsize(size);
+
+    unsafe {
+        check(libc::openpty(&mut master, &mut slave, ptr::null_mut(), ptr::null(), &ws))?;
+        let (master, slave) = (File::from_raw_fd(master), File::from_raw_fd(slave));
+
+        // only the program is meant to inherit the terminal
+        check(libc::fcntl(master.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC))?;
+        check(libc::fcntl(slave.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC))?;
+        Ok((master, slave))
+    }
+}
+
+/// A program running in a pseudo terminal, with its screen kept by an `Emulator`.
+///
+/// Output is read on a thread of its own, which asks the main loop to redraw whenever the
+/// screen changed. Dropping the `Pty` hangs up the terminal, which ends most programs.
+pub struct Pty {
+    master: File,
+    pid: libc::pid_t,
+    emulator: Arc<Mutex<Emulator>>,
+    running: Arc<AtomicBool>,
+}
+
+impl Pty {
+    /// Starts `command` in a new terminal of `size`, which is at least a single cell. The
+    /// action created by `on_exit` is delivered once the program exited.
+    pub fn spawn<A, F>(handle: &LoopHandle<A>,
+                       mut command: Command,
+                       size: Size,
+                       on_exit: F)
+                       -> io::Result<Pty>
+        where A: Send + 'static,
+              F: FnOnce(io::Result<ExitStatus>) -> A + Send + 'static
+    {
+        let size = (max(size.0, 1), max(size.1, 1));
+        let (master, slave) = open(size)?;
+
+        if !command.get_envs().any(|(key, _)| key == "TERM") {
+            command.env("TERM", TERM);
+        }
+        command.stdin(Stdio::from(slave.try_clone()?))
+            .stdout(Stdio::from(slave.try_clone()?))
+            .stderr(Stdio::from(slave));
+
+        unsafe {
+            command.pre_exec(|| {
+                // a session of its own, with the terminal as controlling terminal
+                check(libc::setsid())?;
+                check(libc::ioctl(0, libc::TIOCSCTTY, 0))?;
+                Ok(())
+            });
+        }
+
+        let mut child = command.spawn()?;
+        // the command still holds the program side, which has to be closed for reads to end
+        drop(command);
+
+        let emulator = Arc::new(Mutex::new(Emulator::new(size)));
+        let running = Arc::new(AtomicBool::new(true));
+        let pty = Pty {
+            master: master.try_clone()?,
+            pid: child.id() as libc::pid_t,
+            emulator: emulator.clone(),
+            running: running.clone(),
+        };
+
+        let handle = handle.clone();
+        let mut reader = master;
+        thread::spawn(move || {
+            let mut buf = [0u8; 4096];
+
+            // reading fails with EIO once the program side is closed by all processes
+            while let Ok(n) = reader.read(&mut buf) {
+                if n == 0 {
+                    break;
+                }
+
+                let response = {
+                    let mut emulator = emulator.lock().unwrap();
+                    emulator.feed(&buf[..n]);
+                    emulator.take_response()
+                };
+                if !response.is_empty() && reader.write_all(&response).is_err() {
+                    break;
+                }
+
+                handle.request_redraw();
+            }
+
+            let status = child.wait();
+            running.store(false, Ordering::Relaxed);
+            handle.send(on_exit(status));
+        });
+
+        Ok(pty)
+    }
+
+    /// Sends input to the program, as if typed.
+    pub fn write(&self, input: &[u8]) -> io::Result<()> {
+        (&self.master).write_all(input)
+    }
+
+    /// Sends a key, as received in a `rustty::Event`.
+    pub fn send_key(&self, ch: char) -> io::Result<()> {
+        let mut buf = [0u8; 4];
+        self.write(ch.encode_utf8(&mut buf).as_bytes())
+    }
+
+    /// Changes the size of the terminal. The program is notified by the kernel.
+    /// Empty sizes are ignored.
+    pub fn resize(&self, size: Size) -> io::Result<()> {
+        let mut emulator = self.emulator.lock().unwrap();
+        if size.0 == 0 || size.1 == 0 || emulator.size() == size {
+            return Ok(());
+        }
+
+        let ws = winsize(size);
+        unsafe {
+            check(libc::ioctl(self.master.as_raw_fd(), libc::TIOCSWINSZ, &ws))?;
+        }
+        emulator.resize(size);
+        Ok(())
+    }
+
+    /// The screen of the terminal. Output is not processed while the guard is held.
+    #[inline]
+    pub fn screen(&self) -> MutexGuard<'_, Emulator> {
+        self.emulator.lock().unwrap()
+    }
+
+    #[inline]
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    /// Sends `SIGHUP` to the programs in the terminal, like closing a terminal window does.
+    pub fn hangup(&self) {
+        if self.is_running() {
+            unsafe {
+                libc::kill(-self.pid, libc::SIGHUP);
+            }
+        }
+    }
+}
+
+impl Drop for Pty {
+    fn drop(&mut self) {
+        self.hangup();
+    }
+}
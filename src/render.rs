@@ -0,0 +1,306 @@
+use rustty::{Attr, Pos, Size};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use view::{Buffer, Cell, Color, ColorSupport, Damage};
+
+/// Colors and attribute bits of a cell, as last sent to the terminal.
+type Sgr = (Color, Color, u8);
+
+const BOLD: u8 = Attr::Bold as u8;
+const UNDERLINE: u8 = Attr::Underline as u8;
+const REVERSE: u8 = Attr::Reverse as u8;
+
+/// The colors and attributes sent for `cell`, with colors the terminal lacks replaced.
+fn sgr_of(cell: &Cell, colors: ColorSupport) -> Sgr {
+    let (fg, bg, mut attr) = (cell.fg(), cell.bg(), cell.attrs() as u8);
+
+    // without colors, backgrounds still set cells apart
+    if colors == ColorSupport::Mono && bg != Color::Default {
+        attr ^= REVERSE;
+    }
+
+    (fg.downsample(colors), bg.downsample(colors), attr)
+}
+
+fn color_code(out: &mut Vec<u8>, color: Color, base: u8) {
+    match color {
+        Color::Default => write!(out, "{}", base + 9),
+        Color::Byte(b) if b < 8 => write!(out, "{}", base + b),
+        Color::Byte(b) if b < 16 => write!(out, "{}", base + 60 + b - 8),
+        Color::Byte(b) => write!(out, "{};5;{}", base + 8, b),
+        Color::Rgb(r, g, b) => write!(out, "{};2;{};{};{}", base + 8, r, g, b),
+        c => write!(out, "{}", base + c.index().unwrap()),
+    }
+    .unwrap();
+}
+
+/// Appends a SGR parameter, separated from the previous one.
+fn param<F: FnOnce(&mut Vec<u8>)>(out: &mut Vec<u8>, f: F) {
+    if !out.is_empty() {
+        out.push(b';');
+    }
+    f(out);
+}
+
+/// Parameters switching from `from` to `to` without a reset.
+fn sgr_change(from: Sgr, to: Sgr) -> Vec<u8> {
+    let mut out = Vec::new();
+    let ((ffg, fbg, fattr), (tfg, tbg, tattr)) = (from, to);
+
+    if fattr & BOLD != tattr & BOLD {
+        param(&mut out, |o| o.extend_from_slice(if tattr & BOLD != 0 { b"1" } else { b"22" }));
+    }
+    if fattr & UNDERLINE != tattr & UNDERLINE {
+        param(&mut out,
+              |o| o.extend_from_slice(if tattr & UNDERLINE != 0 { b"4" } else { b"24" }));
+    }
+    if fattr & REVERSE != tattr & REVERSE {
+        param(&mut out,
+              |o| o.extend_from_slice(if tattr & REVERSE != 0 { b"7" } else { b"27" }));
+    }
+    if ffg != tfg {
+        param(&mut out, |o| color_code(o, tfg, 30));
+    }
+    if fbg != tbg {
+        param(&mut out, |o| color_code(o, tbg, 40));
+    }
+
+    out
+}
+
+/// Parameters setting `to` from scratch.
+fn sgr_reset(to: Sgr) -> Vec<u8> {
+    let mut out = b"0".to_vec();
+    let change = sgr_change((Color::Default, Color::Default, 0), to);
+    if !change.is_empty() {
+        out.push(b';');
+        out.extend_from_slice(&change);
+    }
+    out
+}
+
+/// Writes frames to the terminal, sending only what differs from the screen contents.
+///
+/// Only cells recorded in the `Damage` of a frame are compared. Changed cells are written with
+/// the shortest cursor movement and attribute changes possible.
+pub(crate) struct Renderer {
+    tty: File,
+    out: Vec<u8>,
+    colors: ColorSupport,
+
+    size: Size,
+    // what the screen shows, `None` if unknown
+    front: Vec<Option<Cell>>,
+    compare_all: bool,
+
+    // `None` if unknown, e.g. after writing into the last column
+    cursor: Option<Pos>,
+    sgr: Option<Sgr>,
+}
+
+impl Renderer {
+    pub fn new(size: Size, colors: ColorSupport) -> io::Result<Renderer> {
+        let tty = OpenOptions::new().write(true).open("/dev/tty")?;
+        let mut renderer = Renderer {
+            tty,
+            out: Vec::with_capacity(32 * 1024),
+            colors,
+            size: (0, 0),
+            front: Vec::new(),
+            compare_all: true,
+            cursor: None,
+            sgr: None,
+        };
+        renderer.reset(size);
+        Ok(renderer)
+    }
+
+    /// The screen was cleared to blank cells of the default style.
+    pub fn reset(&mut self, size: Size) {
+        self.size = size;
+        self.front.clear();
+        self.front.resize(size.0 * size.1, Some(Cell::default()));
+        self.compare_all = true;
+        self.cursor = None;
+        self.sgr = None;
+    }
+
+    pub fn render(&mut self, buffer: &Buffer, damage: &Damage) -> io::Result<()> {
+        let (cols, rows) = buffer.size();
+        if (cols, rows) != self.size {
+            self.size = (cols, rows);
+            self.front.clear();
+            self.front.resize(cols * rows, None);
+            self.compare_all = true;
+            self.cursor = None;
+        }
+
+        if cols == 0 {
+            return Ok(());
+        }
+
+        let compare_all = self.compare_all || damage.is_full();
+        for y in 0..rows {
+            let (x0, x1) = if compare_all {
+                (0, cols - 1)
+            } else {
+                match damage.span(y) {
+                    Some((l, r)) if l < cols => (l, r.min(cols - 1)),
+                    _ => continue,
+                }
+            };
+
+            for x in x0..x1 + 1 {
+                let cell = buffer[(x, y)];
+                if self.front[y * cols + x] == Some(cell) {
+                    continue;
+                }
+
+                self.move_to(buffer, (x, y));
+                self.set_sgr(sgr_of(&cell, self.colors));
+                self.put(cell);
+            }
+        }
+
+        self.compare_all = false;
+
+        if !self.out.is_empty() {
+            self.tty.write_all(&self.out)?;
+            self.tty.flush()?;
+            self.out.clear();
+        }
+
+        Ok(())
+    }
+
+    /// Writes `cell` at the cursor position and advances the cursor.
+    fn put(&mut self, cell: Cell) {
+        let (x, y) = self.cursor.expect("cursor position unknown");
+        let mut buf = [0u8; 4];
+        self.out.extend_from_slice(cell.ch().encode_utf8(&mut buf).as_bytes());
+        self.front[y * self.size.0 + x] = Some(cell);
+
+        // the cursor stays in the last column until the next character is written, and
+        // terminals differ in what happens then
+        self.cursor = if x + 1 < self.size.0 {
+            Some((x + 1, y))
+        } else {
+            None
+        };
+    }
+
+    fn set_sgr(&mut self, to: Sgr) {
+        let params = match self.sgr {
+            Some(from) if from == to => return,
+            Some(from) => {
+                let change = sgr_change(from, to);
+                let reset = sgr_reset(to);
+                if change.len() <= reset.len() {
+                    change
+                } else {
+                    reset
+                }
+            }
+            None => sgr_reset(to),
+        };
+
+        self.out.extend_from_slice(b"\x1b[");
+        self.out.extend_from_slice(&params);
+        self.out.push(b'm');
+        self.sgr = Some(to);
+    }
+
+    /// Moves the cursor to `to`, choosing the shortest sequence for it.
+    fn move_to(&mut self, buffer: &Buffer, to: Pos) {
+        let (x, y) = to;
+
+        let mut best = Vec::new();
+        if y == 0 && x == 0 {
+            best.extend_from_slice(b"\x1b[H");
+        } else if x == 0 {
+            write!(best, "\x1b[{}H", y + 1).unwrap();
+        } else {
+            write!(best, "\x1b[{};{}H", y + 1, x + 1).unwrap();
+        }
+
+        if let Some((cx, cy)) = self.cursor {
+            if (cx, cy) == to {
+                return;
+            }
+
+            let mut candidates = Vec::new();
+
+            // vertical movement, either from the current column or from the first one
+            let mut vertical = Vec::new();
+            if y > cy {
+                relative(&mut vertical, y - cy, b'B');
+            } else if y < cy {
+                relative(&mut vertical, cy - y, b'A');
+            }
+
+            let mut rel = vertical.clone();
+            if x > cx {
+                relative(&mut rel, x - cx, b'C');
+            } else if x < cx {
+                relative(&mut rel, cx - x, b'D');
+            }
+            candidates.push(rel);
+
+            let mut cr = Vec::new();
+            if y == cy + 1 {
+                cr.extend_from_slice(b"\r\n");
+            } else {
+                cr.push(b'\r');
+                cr.extend_from_slice(&vertical);
+            }
+            if x > 0 {
+                relative(&mut cr, x, b'C');
+            }
+            candidates.push(cr);
+
+            // rewriting unchanged cells of the current style can be shorter than moving
+            if y == cy && x > cx && x - cx <= best.len() {
+                if let Some(text) = self.rewrite(buffer, (cx, cy), x) {
+                    candidates.push(text);
+                }
+            }
+
+            for c in candidates {
+                if c.len() < best.len() {
+                    best = c;
+                }
+            }
+        }
+
+        self.out.extend_from_slice(&best);
+        self.cursor = Some(to);
+    }
+
+    /// The bytes that write the unchanged cells between `from` and column `to` again, if they
+    /// are known, ASCII and use the current style.
+    fn rewrite(&self, buffer: &Buffer, from: Pos, to: usize) -> Option<Vec<u8>> {
+        let (cx, y) = from;
+        let mut text = Vec::with_capacity(to - cx);
+
+        for x in cx..to {
+            let cell = buffer[(x, y)];
+            if self.front[y * self.size.0 + x] != Some(cell) ||
+               Some(sgr_of(&cell, self.colors)) != self.sgr || !cell.ch().is_ascii() {
+                return None;
+            }
+            text.push(cell.ch() as u8);
+        }
+
+        Some(text)
+    }
+}
+
+/// Appends a relative cursor movement by `n` cells, omitting a count of one.
+fn relative(out: &mut Vec<u8>, n: usize, dir: u8) {
+    if n == 1 {
+        out.extend_from_slice(b"\x1b[");
+    } else {
+        write!(out, "\x1b[{}", n).unwrap();
+    }
+    out.push(dir);
+}
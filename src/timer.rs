@@ -0,0 +1,107 @@
+use std::time::{Duration, Instant};
+
+/// Identifies a timer registered through a `LoopHandle`, used to cancel it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TimerId(pub(crate) usize);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Schedule {
+    Once(Duration),
+    Every(Duration),
+    /// Fires once per frame, at the rate set by `MainLoop::min_delay`.
+    Frame,
+}
+
+pub(crate) type ActionFn<A> = Box<dyn FnMut() -> A + Send>;
+
+struct Timer<A> {
+    id: TimerId,
+    deadline: Instant,
+    interval: Option<Duration>,
+    frame: bool,
+    make_action: ActionFn<A>,
+}
+
+/// Pending timers of a main loop. Only a handful of timers are expected to be active at the
+/// same time, so they are kept in a plain vector.
+pub(crate) struct TimerQueue<A> {
+    timers: Vec<Timer<A>>,
+}
+
+impl<A> TimerQueue<A> {
+    pub fn new() -> TimerQueue<A> {
+        TimerQueue { timers: Vec::new() }
+    }
+
+    /// Adds a timer, with `frame_delay` being the interval used for frame ticks.
+    pub fn insert(&mut self,
+                  id: TimerId,
+                  schedule: Schedule,
+                  frame_delay: Duration,
+                  make_action: ActionFn<A>) {
+        let (delay, interval) = match schedule {
+            Schedule::Once(delay) => (delay, None),
+            Schedule::Every(interval) => (interval, Some(interval)),
+            Schedule::Frame => (frame_delay, Some(frame_delay)),
+        };
+
+        // a zero interval would keep the loop spinning
+        let interval = interval.map(|i| i.max(Duration::from_millis(1)));
+
+        self.timers.push(Timer {
+            id,
+            deadline: Instant::now() + delay,
+            interval,
+            frame: schedule == Schedule::Frame,
+            make_action,
+        });
+    }
+
+    pub fn cancel(&mut self, id: TimerId) {
+        self.timers.retain(|t| t.id != id);
+    }
+
+    /// Moves all frame ticks to `next_frame`. Called after each redraw, so that ticks line up
+    /// with the frame rate limit instead of drifting against it.
+    pub fn align_frames(&mut self, next_frame: Instant) {
+        for timer in self.timers.iter_mut().filter(|t| t.frame) {
+            timer.deadline = next_frame;
+        }
+    }
+
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.timers.iter().map(|t| t.deadline).min()
+    }
+
+    /// Collects the actions of all timers due at `now`, in deadline order. Repeating timers are
+    /// rescheduled; if they fell behind, missed intervals are skipped instead of fired in a burst.
+    pub fn expire(&mut self, now: Instant) -> Vec<A> {
+        let mut due: Vec<_> = self.timers
+            .iter()
+            .enumerate()
+            .filter(|&(_, t)| t.deadline <= now)
+            .map(|(idx, t)| (t.deadline, idx))
+            .collect();
+        due.sort();
+
+        let actions = due.iter()
+            .map(|&(_, idx)| {
+                let timer = &mut self.timers[idx];
+                let action = (timer.make_action)();
+
+                if let Some(interval) = timer.interval {
+                    timer.deadline += interval;
+                    if timer.deadline <= now {
+                        timer.deadline = now + interval;
+                    }
+                }
+
+                action
+            })
+            .collect();
+
+        self.timers.retain(|t| t.interval.is_some() || t.deadline > now);
+
+        actions
+    }
+}
@@ -0,0 +1,469 @@
+//! Output of other programs, containing ANSI escape sequences, turned into styled text.
+//!
+//! Colors and the attributes bold, underline and reverse set by SGR sequences are kept. Carriage
+//! returns, backspaces and tabs move within the line as on a terminal, and erasing the line and
+//! horizontal cursor movement are followed. All other escape and control sequences are dropped.
+
+use std::cmp::min;
+use std::str;
+use super::Color;
+use super::text::{Line, Span, Text};
+use super::theme::{attr_from_bits, StyleSpec};
+
+/// Longest parameter list of a control sequence that is kept; longer ones are ignored.
+const MAX_PARAMS: usize = 64;
+
+/// Furthest column the cursor is moved to by control sequences.
+const MAX_WIDTH: usize = 4096;
+
+const TAB_WIDTH: usize = 8;
+
+pub(crate) const BOLD: u8 = 1;
+pub(crate) const UNDERLINE: u8 = 2;
+pub(crate) const REVERSE: u8 = 4;
+
+/// A control sequence: `ESC [`, parameters, an optional intermediate byte and the command.
+pub(crate) struct Csi<'p> {
+    pub params: &'p [u8],
+    pub intermediate: Option<u8>,
+    pub cmd: u8,
+}
+
+impl<'p> Csi<'p> {
+    /// The marker of private sequences like `ESC [ ? 25 h`.
+    pub fn private(&self) -> Option<u8> {
+        match self.params.first() {
+            Some(&b @ b'<'..=b'?') => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Numeric parameters, missing ones given as `None`. Sub-parameters separated by colons
+    /// are treated like separate parameters.
+    pub fn numbers(&self) -> Vec<Option<u32>> {
+        let params = if self.private().is_some() {
+            &self.params[1..]
+        } else {
+            self.params
+        };
+
+        params.split(|&b| b == b';' || b == b':')
+            .map(|p| {
+                p.iter()
+                    .try_fold(0u32, |n, &b| if b.is_ascii_digit() {
+                        Some(n.saturating_mul(10).saturating_add(u32::from(b - b'0')))
+                    } else {
+                        None
+                    })
+                    .filter(|_| !p.is_empty())
+            })
+            .collect()
+    }
+
+    /// Parameter `idx`, with `default` for missing and zero values, as most commands expect.
+    pub fn arg(&self, idx: usize, default: u32) -> u32 {
+        match self.numbers().get(idx) {
+            Some(&Some(n)) if n > 0 => n,
+            _ => default,
+        }
+    }
+}
+
+/// Receives the pieces a `Tokenizer` splits its input into.
+pub(crate) trait Perform {
+    fn print(&mut self, ch: char);
+
+    /// A C0 control character.
+    fn execute(&mut self, byte: u8);
+
+    fn csi(&mut self, csi: &Csi);
+
+    /// Any other escape sequence, like `ESC 7` or `ESC ( B`.
+    fn esc(&mut self, intermediate: Option<u8>, cmd: u8);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    Ground,
+    Escape,
+    Csi,
+    // sequences that are too long or malformed, skipped up to their end
+    CsiIgnore,
+    // string sequences like OSC, terminated by BEL or `ESC \`
+    Str,
+    StrEscape,
+}
+
+/// Splits a byte stream into characters and escape sequences, which may arrive in chunks of
+/// any size. Invalid UTF-8 is replaced by U+FFFD.
+#[derive(Clone, Debug)]
+pub(crate) struct Tokenizer {
+    state: State,
+    params: Vec<u8>,
+    intermediate: Option<u8>,
+    utf8: Vec<u8>,
+}
+
+impl Tokenizer {
+    pub fn new() -> Tokenizer {
+        Tokenizer {
+            state: State::Ground,
+            params: Vec::new(),
+            intermediate: None,
+            utf8: Vec::with_capacity(4),
+        }
+    }
+
+    pub fn feed<P: Perform>(&mut self, input: &[u8], perform: &mut P) {
+        for &byte in input {
+            self.byte(byte, perform);
+        }
+    }
+
+    fn byte<P: Perform>(&mut self, byte: u8, perform: &mut P) {
+        match self.state {
+            State::Ground => self.ground(byte, perform),
+            State::Escape => {
+                match byte {
+                    b'[' if self.intermediate.is_none() => {
+                        self.params.clear();
+                        self.state = State::Csi;
+                    }
+                    b']' | b'P' | b'X' | b'^' | b'_' if self.intermediate.is_none() => {
+                        self.state = State::Str
+                    }
+                    0x20..=0x2f => self.intermediate = Some(byte),
+                    0x30..=0x7e => {
+                        self.state = State::Ground;
+                        perform.esc(self.intermediate, byte);
+                    }
+                    0x1b => self.intermediate = None,
+                    0x00..=0x1f => perform.execute(byte),
+                    _ => self.state = State::Ground,
+                }
+            }
+            State::Csi | State::CsiIgnore => {
+                match byte {
+                    0x30..=0x3f if self.intermediate.is_none() => {
+                        if self.params.len() < MAX_PARAMS {
+                            self.params.push(byte);
+                        } else {
+                            self.state = State::CsiIgnore;
+                        }
+                    }
+                    0x20..=0x2f if self.intermediate.is_none() => self.intermediate = Some(byte),
+                    0x40..=0x7e => {
+                        if self.state == State::Csi {
+                            perform.csi(&Csi {
+                                params: &self.params,
+                                intermediate: self.intermediate,
+                                cmd: byte,
+                            });
+                        }
+                        self.state = State::Ground;
+                    }
+                    0x1b => self.escape(),
+                    // control characters are executed in the middle of sequences
+                    0x00..=0x1f => perform.execute(byte),
+                    0x20..=0x3f => self.state = State::CsiIgnore,
+                    _ => self.state = State::Ground,
+                }
+            }
+            State::Str => {
+                match byte {
+                    0x07 => self.state = State::Ground,
+                    0x1b => self.state = State::StrEscape,
+                    _ => (),
+                }
+            }
+            State::StrEscape => {
+                self.state = if byte == b'\\' {
+                    State::Ground
+                } else {
+                    State::Str
+                };
+            }
+        }
+    }
+
+    #[inline]
+    fn escape(&mut self) {
+        self.state = State::Escape;
+        self.intermediate = None;
+    }
+
+    fn ground<P: Perform>(&mut self, byte: u8, perform: &mut P) {
+        if !self.utf8.is_empty() {
+            if byte & 0xc0 == 0x80 {
+                self.utf8.push(byte);
+                if self.utf8.len() == utf8_len(self.utf8[0]) {
+                    let ch = str::from_utf8(&self.utf8)
+                        .ok()
+                        .and_then(|s| s.chars().next())
+                        .unwrap_or('\u{fffd}');
+                    self.utf8.clear();
+                    perform.print(ch);
+                }
+                return;
+            }
+
+            // cut short by something else
+            self.utf8.clear();
+            perform.print('\u{fffd}');
+        }
+
+        match byte {
+            0x1b => self.escape(),
+            0x00..=0x1f | 0x7f => perform.execute(byte),
+            0x20..=0x7e => perform.print(byte as char),
+            _ if utf8_len(byte) > 1 => self.utf8.push(byte),
+            _ => perform.print('\u{fffd}'),
+        }
+    }
+}
+
+/// Length of the UTF-8 sequence started by `byte`, or 0 if it does not start one.
+#[inline]
+fn utf8_len(byte: u8) -> usize {
+    match byte {
+        0x00..=0x7f => 1,
+        0xc2..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf4 => 4,
+        _ => 0,
+    }
+}
+
+/// Colors and attributes set by SGR sequences, unset parts left to the surroundings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Sgr {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub attrs: u8,
+}
+
+impl Sgr {
+    pub fn style(&self) -> StyleSpec {
+        StyleSpec {
+            fg: self.fg,
+            bg: self.bg,
+            attr: if self.attrs == 0 {
+                None
+            } else {
+                Some(attr_from_bits(self.attrs))
+            },
+        }
+    }
+
+    pub fn apply(&mut self, numbers: &[Option<u32>]) {
+        if numbers.is_empty() {
+            *self = Sgr::default();
+            return;
+        }
+
+        let mut iter = numbers.iter().map(|n| n.unwrap_or(0));
+        while let Some(n) = iter.next() {
+            match n {
+                0 => *self = Sgr::default(),
+                1 => self.attrs |= BOLD,
+                4 => self.attrs |= UNDERLINE,
+                7 => self.attrs |= REVERSE,
+                22 => self.attrs &= !BOLD,
+                24 => self.attrs &= !UNDERLINE,
+                27 => self.attrs &= !REVERSE,
+                30..=37 => self.fg = Some(Color::Byte((n - 30) as u8)),
+                38 => self.fg = extended_color(&mut iter).or(self.fg),
+                39 => self.fg = None,
+                40..=47 => self.bg = Some(Color::Byte((n - 40) as u8)),
+                48 => self.bg = extended_color(&mut iter).or(self.bg),
+                49 => self.bg = None,
+                90..=97 => self.fg = Some(Color::Byte((n - 90 + 8) as u8)),
+                100..=107 => self.bg = Some(Color::Byte((n - 100 + 8) as u8)),
+                // dim, italic, blinking and others cannot be shown
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Reads the rest of an extended color, `5;n` or `2;r;g;b`.
+fn extended_color<I: Iterator<Item = u32>>(iter: &mut I) -> Option<Color> {
+    let byte = |n: Option<u32>| n.filter(|&n| n < 256).map(|n| n as u8);
+
+    match iter.next() {
+        Some(5) => byte(iter.next()).map(Color::Byte),
+        Some(2) => {
+            let (r, g, b) = (byte(iter.next()), byte(iter.next()), byte(iter.next()));
+            Some(Color::Rgb(r?, g?, b?))
+        }
+        _ => None,
+    }
+}
+
+/// Lines collected by an `AnsiParser`.
+#[derive(Clone, Debug)]
+struct Lines {
+    sgr: Sgr,
+    lines: Vec<Vec<(char, StyleSpec)>>,
+    col: usize,
+}
+
+impl Lines {
+    fn put(&mut self, ch: char) {
+        let style = self.sgr.style();
+        let line = self.lines.last_mut().unwrap();
+
+        while line.len() < self.col {
+            line.push((' ', StyleSpec::new()));
+        }
+        if self.col < line.len() {
+            line[self.col] = (ch, style);
+        } else {
+            line.push((ch, style));
+        }
+        self.col += 1;
+    }
+}
+
+impl Perform for Lines {
+    fn print(&mut self, ch: char) {
+        self.put(if ch.is_ascii() { ch } else { '?' });
+    }
+
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            b'\n' => {
+                self.lines.push(Vec::new());
+                self.col = 0;
+            }
+            b'\r' => self.col = 0,
+            0x08 => self.col = self.col.saturating_sub(1),
+            b'\t' => {
+                let next = (self.col / TAB_WIDTH + 1) * TAB_WIDTH;
+                while self.col < next {
+                    self.put(' ');
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn csi(&mut self, csi: &Csi) {
+        // private sequences and those with intermediates are not about the text
+        if csi.private().is_some() || csi.intermediate.is_some() {
+            return;
+        }
+
+        let count = csi.arg(0, 1) as usize;
+        match csi.cmd {
+            b'm' => self.sgr.apply(&csi.numbers()),
+            b'K' => {
+                let line = self.lines.last_mut().unwrap();
+                match csi.numbers().first().cloned().unwrap_or(None).unwrap_or(0) {
+                    0 => line.truncate(self.col),
+                    1 => {
+                        for cell in line.iter_mut().take(self.col + 1) {
+                            *cell = (' ', StyleSpec::new());
+                        }
+                    }
+                    2 => line.clear(),
+                    _ => (),
+                }
+            }
+            b'C' => self.col = min(self.col + count, MAX_WIDTH),
+            b'D' => self.col = self.col.saturating_sub(count),
+            b'G' => self.col = min(count - 1, MAX_WIDTH),
+            _ => (),
+        }
+    }
+
+    fn esc(&mut self, _intermediate: Option<u8>, _cmd: u8) {}
+}
+
+/// Parses text containing escape sequences, which may arrive in chunks of any size.
+///
+/// Characters beyond ASCII are replaced by `?`, as they cannot be drawn yet.
+#[derive(Clone, Debug)]
+pub struct AnsiParser {
+    tokenizer: Tokenizer,
+    lines: Lines,
+}
+
+impl Default for AnsiParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AnsiParser {
+    pub fn new() -> AnsiParser {
+        AnsiParser {
+            tokenizer: Tokenizer::new(),
+            lines: Lines {
+                sgr: Sgr::default(),
+                lines: vec![Vec::new()],
+                col: 0,
+            },
+        }
+    }
+
+    /// The style set by the sequences so far. Unset parts are left to the surroundings.
+    #[inline]
+    pub fn style(&self) -> StyleSpec {
+        self.lines.sgr.style()
+    }
+
+    /// Number of lines, including the one still being written.
+    #[inline]
+    pub fn num_lines(&self) -> usize {
+        self.lines.lines.len()
+    }
+
+    #[inline]
+    pub fn feed(&mut self, input: &[u8]) {
+        self.tokenizer.feed(input, &mut self.lines);
+    }
+
+    /// The text parsed so far.
+    pub fn text(&self) -> Text<'static> {
+        Text::new(self.lines.lines.iter().map(|cells| to_line(cells)).collect())
+    }
+
+    /// Drops the lines parsed so far, except for the one still being written. The current
+    /// style is kept.
+    pub fn clear(&mut self) {
+        let last = self.lines.lines.pop().unwrap();
+        self.lines.lines.clear();
+        self.lines.lines.push(last);
+    }
+}
+
+/// Joins cells of the same style into spans.
+fn to_line(cells: &[(char, StyleSpec)]) -> Line<'static> {
+    let mut line = Line::default();
+    let mut current: Option<(String, StyleSpec)> = None;
+
+    for &(ch, style) in cells {
+        match current {
+            Some((ref mut content, s)) if s == style => content.push(ch),
+            _ => {
+                if let Some((content, s)) = current.take() {
+                    line.spans.push(Span::styled(content, s));
+                }
+                current = Some((ch.to_string(), style));
+            }
+        }
+    }
+
+    if let Some((content, s)) = current {
+        line.spans.push(Span::styled(content, s));
+    }
+    line
+}
+
+/// Parses `s` at once, see `AnsiParser`.
+pub fn parse_ansi(s: &str) -> Text<'static> {
+    let mut parser = AnsiParser::new();
+    parser.feed(s.as_bytes());
+    parser.text()
+}
@@ -0,0 +1,136 @@
+//! Box-drawing lines that join with the line glyphs already on screen.
+//!
+//! A glyph is described by its arms going up, right, down and left, each with a weight. Drawing
+//! a line over an existing glyph merges the arms of both, so crossing lines become `┼` and a
+//! line ending on a frame becomes a `├` instead of overwriting it.
+
+/// Weight of box-drawing lines. `Rounded` is light with rounded corners.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineStyle {
+    #[default]
+    Light,
+    Heavy,
+    Double,
+    Rounded,
+}
+
+impl LineStyle {
+    #[inline]
+    fn weight(self) -> u8 {
+        match self {
+            LineStyle::Light | LineStyle::Rounded => LIGHT,
+            LineStyle::Heavy => HEAVY,
+            LineStyle::Double => DOUBLE,
+        }
+    }
+}
+
+const NONE: u8 = 0;
+const LIGHT: u8 = 1;
+const HEAVY: u8 = 2;
+const DOUBLE: u8 = 3;
+
+pub(crate) const UP: usize = 0;
+pub(crate) const RIGHT: usize = 1;
+pub(crate) const DOWN: usize = 2;
+pub(crate) const LEFT: usize = 3;
+
+/// Arms of a glyph, indexed by `UP`, `RIGHT`, `DOWN` and `LEFT`.
+type Arms = [u8; 4];
+
+#[rustfmt::skip]
+const GLYPHS: &[(char, Arms)] = &[
+    ('─', [0, 1, 0, 1]), ('━', [0, 2, 0, 2]), ('│', [1, 0, 1, 0]), ('┃', [2, 0, 2, 0]),
+    ('┌', [0, 1, 1, 0]), ('┍', [0, 2, 1, 0]), ('┎', [0, 1, 2, 0]), ('┏', [0, 2, 2, 0]),
+    ('┐', [0, 0, 1, 1]), ('┑', [0, 0, 1, 2]), ('┒', [0, 0, 2, 1]), ('┓', [0, 0, 2, 2]),
+    ('└', [1, 1, 0, 0]), ('┕', [1, 2, 0, 0]), ('┖', [2, 1, 0, 0]), ('┗', [2, 2, 0, 0]),
+    ('┘', [1, 0, 0, 1]), ('┙', [1, 0, 0, 2]), ('┚', [2, 0, 0, 1]), ('┛', [2, 0, 0, 2]),
+    ('├', [1, 1, 1, 0]), ('┝', [1, 2, 1, 0]), ('┞', [2, 1, 1, 0]), ('┟', [1, 1, 2, 0]),
+    ('┠', [2, 1, 2, 0]), ('┡', [2, 2, 1, 0]), ('┢', [1, 2, 2, 0]), ('┣', [2, 2, 2, 0]),
+    ('┤', [1, 0, 1, 1]), ('┥', [1, 0, 1, 2]), ('┦', [2, 0, 1, 1]), ('┧', [1, 0, 2, 1]),
+    ('┨', [2, 0, 2, 1]), ('┩', [2, 0, 1, 2]), ('┪', [1, 0, 2, 2]), ('┫', [2, 0, 2, 2]),
+    ('┬', [0, 1, 1, 1]), ('┭', [0, 1, 1, 2]), ('┮', [0, 2, 1, 1]), ('┯', [0, 2, 1, 2]),
+    ('┰', [0, 1, 2, 1]), ('┱', [0, 1, 2, 2]), ('┲', [0, 2, 2, 1]), ('┳', [0, 2, 2, 2]),
+    ('┴', [1, 1, 0, 1]), ('┵', [1, 1, 0, 2]), ('┶', [1, 2, 0, 1]), ('┷', [1, 2, 0, 2]),
+    ('┸', [2, 1, 0, 1]), ('┹', [2, 1, 0, 2]), ('┺', [2, 2, 0, 1]), ('┻', [2, 2, 0, 2]),
+    ('┼', [1, 1, 1, 1]), ('┽', [1, 1, 1, 2]), ('┾', [1, 2, 1, 1]), ('┿', [1, 2, 1, 2]),
+    ('╀', [2, 1, 1, 1]), ('╁', [1, 1, 2, 1]), ('╂', [2, 1, 2, 1]), ('╃', [2, 1, 1, 2]),
+    ('╄', [2, 2, 1, 1]), ('╅', [1, 1, 2, 2]), ('╆', [1, 2, 2, 1]), ('╇', [2, 2, 1, 2]),
+    ('╈', [1, 2, 2, 2]), ('╉', [2, 1, 2, 2]), ('╊', [2, 2, 2, 1]), ('╋', [2, 2, 2, 2]),
+    ('═', [0, 3, 0, 3]), ('║', [3, 0, 3, 0]), ('╒', [0, 3, 1, 0]), ('╓', [0, 1, 3, 0]),
+    ('╔', [0, 3, 3, 0]), ('╕', [0, 0, 1, 3]), ('╖', [0, 0, 3, 1]), ('╗', [0, 0, 3, 3]),
+    ('╘', [1, 3, 0, 0]), ('╙', [3, 1, 0, 0]), ('╚', [3, 3, 0, 0]), ('╛', [1, 0, 0, 3]),
+    ('╜', [3, 0, 0, 1]), ('╝', [3, 0, 0, 3]), ('╞', [1, 3, 1, 0]), ('╟', [3, 1, 3, 0]),
+    ('╠', [3, 3, 3, 0]), ('╡', [1, 0, 1, 3]), ('╢', [3, 0, 3, 1]), ('╣', [3, 0, 3, 3]),
+    ('╤', [0, 3, 1, 3]), ('╥', [0, 1, 3, 1]), ('╦', [0, 3, 3, 3]), ('╧', [1, 3, 0, 3]),
+    ('╨', [3, 1, 0, 1]), ('╩', [3, 3, 0, 3]), ('╪', [1, 3, 1, 3]), ('╫', [3, 1, 3, 1]),
+    ('╬', [3, 3, 3, 3]), ('╴', [0, 0, 0, 1]), ('╵', [1, 0, 0, 0]), ('╶', [0, 1, 0, 0]),
+    ('╷', [0, 0, 1, 0]), ('╸', [0, 0, 0, 2]), ('╹', [2, 0, 0, 0]), ('╺', [0, 2, 0, 0]),
+    ('╻', [0, 0, 2, 0]), ('╼', [0, 2, 0, 1]), ('╽', [1, 0, 2, 0]), ('╾', [0, 1, 0, 2]),
+    ('╿', [2, 0, 1, 0]),
+    // rounded corners are only read, `encode` picks them for `LineStyle::Rounded`
+    ('╭', [0, 1, 1, 0]), ('╮', [0, 0, 1, 1]), ('╯', [1, 0, 0, 1]), ('╰', [1, 1, 0, 0]),
+];
+
+/// The arms of `ch`, or `None` if it is not a line glyph.
+fn decode(ch: char) -> Option<Arms> {
+    GLYPHS.iter().find(|&&(g, _)| g == ch).map(|&(_, arms)| arms)
+}
+
+fn lookup(arms: Arms) -> Option<char> {
+    GLYPHS.iter().find(|&&(_, a)| a == arms).map(|&(g, _)| g)
+}
+
+fn encode(arms: Arms, line: LineStyle) -> Option<char> {
+    if line == LineStyle::Rounded {
+        let rounded = match arms {
+            [0, 1, 1, 0] => Some('╭'),
+            [0, 0, 1, 1] => Some('╮'),
+            [1, 0, 0, 1] => Some('╯'),
+            [1, 1, 0, 0] => Some('╰'),
+            _ => None,
+        };
+        if rounded.is_some() {
+            return rounded;
+        }
+    }
+
+    lookup(arms).or_else(|| {
+        // some mixes have no glyph, e.g. heavy with double: the new weight wins
+        let weight = line.weight();
+        let mut arms = arms;
+        for arm in &mut arms {
+            if *arm != NONE {
+                *arm = weight;
+            }
+        }
+        lookup(arms)
+    })
+}
+
+/// Returns whether `ch` is a line glyph that lines drawn over it join with.
+#[inline]
+pub(crate) fn is_line(ch: char) -> bool {
+    decode(ch).is_some()
+}
+
+/// The glyph for `existing` with the arms in `dirs` of weight `line` added.
+pub(crate) fn join(existing: char, dirs: &[usize], line: LineStyle) -> char {
+    let mut arms = decode(existing).unwrap_or([NONE; 4]);
+    for &dir in dirs {
+        arms[dir] = line.weight();
+    }
+
+    encode(arms, line).unwrap_or_else(|| {
+        // a lone double arm has no glyph, fall back to a full line
+        let horizontal = dirs.iter().all(|&d| d == LEFT || d == RIGHT);
+        match (line.weight(), horizontal) {
+            (DOUBLE, true) => '═',
+            (DOUBLE, false) => '║',
+            (HEAVY, true) => '━',
+            (HEAVY, false) => '┃',
+            (_, true) => '─',
+            (_, false) => '│',
+        }
+    })
+}
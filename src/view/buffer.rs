@@ -0,0 +1,133 @@
+use rustty::{Attr, Pos, Size};
+use std::ops::{Index, IndexMut};
+use super::Color;
+
+/// A character with its colors and attributes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cell {
+    ch: char,
+    fg: Color,
+    bg: Color,
+    attrs: Attr,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Cell::new(' ', Color::Default, Color::Default, Attr::Default)
+    }
+}
+
+impl Cell {
+    #[inline]
+    pub fn new(ch: char, fg: Color, bg: Color, attrs: Attr) -> Cell {
+        Cell { ch, fg, bg, attrs }
+    }
+
+    #[inline(always)]
+    pub fn ch(&self) -> char {
+        self.ch
+    }
+
+    #[inline]
+    pub fn set_ch(&mut self, ch: char) -> &mut Cell {
+        self.ch = ch;
+        self
+    }
+
+    #[inline(always)]
+    pub fn fg(&self) -> Color {
+        self.fg
+    }
+
+    #[inline]
+    pub fn set_fg(&mut self, fg: Color) -> &mut Cell {
+        self.fg = fg;
+        self
+    }
+
+    #[inline(always)]
+    pub fn bg(&self) -> Color {
+        self.bg
+    }
+
+    #[inline]
+    pub fn set_bg(&mut self, bg: Color) -> &mut Cell {
+        self.bg = bg;
+        self
+    }
+
+    #[inline(always)]
+    pub fn attrs(&self) -> Attr {
+        self.attrs
+    }
+
+    #[inline]
+    pub fn set_attrs(&mut self, attrs: Attr) -> &mut Cell {
+        self.attrs = attrs;
+        self
+    }
+}
+
+/// The cells of a frame, drawn into by a `DrawingContext`.
+#[derive(Clone, Debug)]
+pub struct Buffer {
+    size: Size,
+    cells: Vec<Cell>,
+}
+
+impl Buffer {
+    /// Creates a buffer of blank cells.
+    pub fn new(size: Size) -> Buffer {
+        Buffer {
+            size,
+            cells: vec![Cell::default(); size.0 * size.1],
+        }
+    }
+
+    #[inline(always)]
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    #[inline(always)]
+    pub fn cols(&self) -> usize {
+        self.size.0
+    }
+
+    #[inline(always)]
+    pub fn rows(&self) -> usize {
+        self.size.1
+    }
+
+    /// Changes the size, clearing all cells.
+    pub fn resize(&mut self, size: Size) {
+        self.size = size;
+        self.cells.clear();
+        self.cells.resize(size.0 * size.1, Cell::default());
+    }
+
+    /// Sets all cells to `cell`.
+    pub fn clear(&mut self, cell: Cell) {
+        for c in &mut self.cells {
+            *c = cell;
+        }
+    }
+}
+
+impl Index<Pos> for Buffer {
+    type Output = Cell;
+
+    #[inline]
+    fn index(&self, (x, y): Pos) -> &Cell {
+        assert!(x < self.size.0 && y < self.size.1, "position out of bounds");
+        &self.cells[y * self.size.0 + x]
+    }
+}
+
+impl IndexMut<Pos> for Buffer {
+    #[inline]
+    fn index_mut(&mut self, (x, y): Pos) -> &mut Cell {
+        assert!(x < self.size.0 && y < self.size.1, "position out of bounds");
+        &mut self.cells[y * self.size.0 + x]
+    }
+}
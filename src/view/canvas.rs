@@ -0,0 +1,319 @@
+use std::f64::consts::PI;
+use super::{Cell, Color, DrawingContext, Style, Widget};
+use super::theme::roles;
+
+/// How the dots of a `Canvas` are made up of characters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Marker {
+    /// Braille patterns with 2x4 dots per cell. All dots in a cell share one color.
+    #[default]
+    Braille,
+    /// Upper and lower half blocks with 1x2 dots per cell, each with a color of its own.
+    HalfBlock,
+}
+
+impl Marker {
+    /// Dots per cell, horizontally and vertically.
+    #[inline]
+    pub fn resolution(self) -> (usize, usize) {
+        match self {
+            Marker::Braille => (2, 4),
+            Marker::HalfBlock => (1, 2),
+        }
+    }
+}
+
+/// Bits of the braille dots, indexed by row and column within the cell.
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+const BRAILLE_BLANK: u32 = 0x2800;
+
+/// Surface a `Canvas` is painted on, addressed in world coordinates.
+///
+/// The world spans the bounds set on the canvas, with `y` growing upwards. Everything outside
+/// the bounds is clipped. `Color::Default` paints with the foreground of the canvas style.
+pub struct Painter {
+    width: usize,
+    height: usize,
+    dots: Vec<Option<Color>>,
+    labels: Vec<(usize, usize, String, Color)>,
+    x_bounds: (f64, f64),
+    y_bounds: (f64, f64),
+    marker: Marker,
+}
+
+impl Painter {
+    pub(crate) fn new(size: (usize, usize),
+                      marker: Marker,
+                      x_bounds: (f64, f64),
+                      y_bounds: (f64, f64))
+                      -> Painter {
+        let (dx, dy) = marker.resolution();
+        let (width, height) = (size.0 * dx, size.1 * dy);
+
+        Painter {
+            width,
+            height,
+            dots: vec![None; width * height],
+            labels: Vec::new(),
+            x_bounds,
+            y_bounds,
+            marker,
+        }
+    }
+
+    /// Number of dots horizontally and vertically.
+    #[inline]
+    pub fn resolution(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Converts world coordinates into fractional dot coordinates, which may lie outside.
+    fn project(&self, x: f64, y: f64) -> (f64, f64) {
+        let (x0, x1) = self.x_bounds;
+        let (y0, y1) = self.y_bounds;
+        let sx = if x1 != x0 { (x - x0) / (x1 - x0) } else { 0.0 };
+        let sy = if y1 != y0 { (y1 - y) / (y1 - y0) } else { 0.0 };
+
+        (sx * (self.width as f64 - 1.0), sy * (self.height as f64 - 1.0))
+    }
+
+    /// The dot at world coordinates `(x, y)`, if inside the bounds.
+    pub fn dot(&self, x: f64, y: f64) -> Option<(usize, usize)> {
+        let (px, py) = self.project(x, y);
+        let (px, py) = (px.round(), py.round());
+
+        if px >= 0.0 && py >= 0.0 && px < self.width as f64 && py < self.height as f64 {
+            Some((px as usize, py as usize))
+        } else {
+            None
+        }
+    }
+
+    /// Sets the dot at `(x, y)` in dot coordinates, as returned by `dot`.
+    #[inline]
+    pub fn paint(&mut self, x: usize, y: usize, color: Color) {
+        if x < self.width && y < self.height {
+            self.dots[y * self.width + x] = Some(color);
+        }
+    }
+
+    pub fn point(&mut self, x: f64, y: f64, color: Color) {
+        if let Some((px, py)) = self.dot(x, y) {
+            self.paint(px, py, color);
+        }
+    }
+
+    pub fn line(&mut self, from: (f64, f64), to: (f64, f64), color: Color) {
+        let a = self.project(from.0, from.1);
+        let b = self.project(to.0, to.1);
+        let (a, b) = match self.clip(a, b) {
+            Some(ends) => ends,
+            None => return,
+        };
+
+        let (x0, y0) = (a.0.round() as isize, a.1.round() as isize);
+        let (x1, y1) = (b.0.round() as isize, b.1.round() as isize);
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (sx, sy) = ((x1 - x0).signum(), (y1 - y0).signum());
+        let (mut x, mut y, mut err) = (x0, y0, dx + dy);
+
+        loop {
+            self.paint(x as usize, y as usize, color);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    /// Clips the segment between dot coordinates `a` and `b` to the dots of the painter.
+    fn clip(&self, a: (f64, f64), b: (f64, f64)) -> Option<((f64, f64), (f64, f64))> {
+        let (max_x, max_y) = (self.width as f64 - 1.0, self.height as f64 - 1.0);
+        if max_x < 0.0 || max_y < 0.0 {
+            return None;
+        }
+
+        // Liang-Barsky: narrow the parameter range of the segment by each edge in turn
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let edges = [(-dx, a.0), (dx, max_x - a.0), (-dy, a.1), (dy, max_y - a.1)];
+        let (mut t0, mut t1) = (0.0f64, 1.0f64);
+
+        for &(p, q) in &edges {
+            if p == 0.0 {
+                if q < 0.0 {
+                    return None;
+                }
+            } else if p < 0.0 {
+                t0 = t0.max(q / p);
+            } else {
+                t1 = t1.min(q / p);
+            }
+        }
+
+        if t0 > t1 {
+            return None;
+        }
+        Some(((a.0 + t0 * dx, a.1 + t0 * dy), (a.0 + t1 * dx, a.1 + t1 * dy)))
+    }
+
+    /// Draws the outline of a polygon through `points`, closing it back to the first one.
+    pub fn polygon(&mut self, points: &[(f64, f64)], color: Color) {
+        for (i, &a) in points.iter().enumerate() {
+            let b = points[(i + 1) % points.len()];
+            self.line(a, b, color);
+        }
+    }
+
+    /// Draws a circle of `radius` in world units around `center`. If the bounds are scaled
+    /// differently on both axes, the circle is drawn as such an ellipse.
+    pub fn circle(&mut self, center: (f64, f64), radius: f64, color: Color) {
+        let (cx, cy) = self.project(center.0, center.1);
+        let (ex, ey) = self.project(center.0 + radius, center.1 + radius);
+        let extent = (ex - cx).abs().max((ey - cy).abs());
+
+        // enough segments for every step to stay within about a dot
+        let steps = ((2.0 * PI * extent).ceil() as usize).clamp(8, 4096);
+        let points: Vec<(f64, f64)> = (0..steps)
+            .map(|i| {
+                let angle = 2.0 * PI * i as f64 / steps as f64;
+                (center.0 + radius * angle.cos(), center.1 + radius * angle.sin())
+            })
+            .collect();
+        self.polygon(&points, color);
+    }
+
+    /// Prints `text` starting in the cell containing `(x, y)`, on top of the dots.
+    pub fn print<S: Into<String>>(&mut self, x: f64, y: f64, text: S, color: Color) {
+        let text = text.into();
+        assert!(text.is_ascii());
+
+        if let Some((px, py)) = self.dot(x, y) {
+            let (dx, dy) = self.marker.resolution();
+            self.labels.push((px / dx, py / dy, text, color));
+        }
+    }
+
+    #[inline]
+    fn get(&self, x: usize, y: usize) -> Option<Color> {
+        self.dots[y * self.width + x]
+    }
+
+    pub(crate) fn draw_on(&self, ctx: &mut DrawingContext, style: Style) {
+        let (cols, rows) = ctx.size();
+        let fg = |color: Color| if color == Color::Default { style.0 } else { color };
+
+        for row in 0..rows {
+            for col in 0..cols {
+                let cell = match self.marker {
+                    Marker::Braille => {
+                        let mut bits = 0;
+                        let mut color = None;
+                        for (dy, dots) in BRAILLE_DOTS.iter().enumerate() {
+                            for (dx, &bit) in dots.iter().enumerate() {
+                                if let Some(c) = self.get(col * 2 + dx, row * 4 + dy) {
+                                    bits |= bit;
+                                    color = Some(c);
+                                }
+                            }
+                        }
+                        let ch = ::std::char::from_u32(BRAILLE_BLANK + bits).unwrap();
+                        match color {
+                            Some(c) => Cell::new(ch, fg(c), style.1, style.2),
+                            None => Cell::new(' ', style.0, style.1, style.2),
+                        }
+                    }
+                    Marker::HalfBlock => {
+                        match (self.get(col, row * 2), self.get(col, row * 2 + 1)) {
+                            (Some(top), Some(bottom)) => {
+                                Cell::new('▀', fg(top), fg(bottom), style.2)
+                            }
+                            (Some(top), None) => Cell::new('▀', fg(top), style.1, style.2),
+                            (None, Some(bottom)) => {
+                                Cell::new('▄', fg(bottom), style.1, style.2)
+                            }
+                            (None, None) => Cell::new(' ', style.0, style.1, style.2),
+                        }
+                    }
+                };
+                ctx.set_cell((col, row), cell);
+            }
+        }
+
+        for &(x, y, ref text, color) in &self.labels {
+            ctx.text((x, y), text, (fg(color), style.1, style.2));
+        }
+    }
+}
+
+/// A surface for points, lines, circles and polygons at a resolution finer than cells.
+///
+/// Shapes are painted by a closure in world coordinates, which are mapped onto the area of the
+/// canvas: `x_bounds` from left to right and `y_bounds` from bottom to top.
+pub struct Canvas<'a> {
+    paint: Box<dyn Fn(&mut Painter) + 'a>,
+    x_bounds: (f64, f64),
+    y_bounds: (f64, f64),
+    marker: Marker,
+    style: Option<Style>,
+}
+
+impl<'a> Canvas<'a> {
+    pub fn new<F: Fn(&mut Painter) + 'a>(paint: F) -> Canvas<'a> {
+        Canvas {
+            paint: Box::new(paint),
+            x_bounds: (0.0, 1.0),
+            y_bounds: (0.0, 1.0),
+            marker: Marker::Braille,
+            style: None,
+        }
+    }
+
+    /// World coordinates of the left and right edge, `(0.0, 1.0)` by default.
+    #[inline]
+    pub fn x_bounds(mut self, left: f64, right: f64) -> Canvas<'a> {
+        self.x_bounds = (left, right);
+        self
+    }
+
+    /// World coordinates of the bottom and top edge, `(0.0, 1.0)` by default.
+    #[inline]
+    pub fn y_bounds(mut self, bottom: f64, top: f64) -> Canvas<'a> {
+        self.y_bounds = (bottom, top);
+        self
+    }
+
+    #[inline]
+    pub fn marker(mut self, marker: Marker) -> Canvas<'a> {
+        self.marker = marker;
+        self
+    }
+
+    #[inline]
+    pub fn style(mut self, style: Style) -> Canvas<'a> {
+        self.style = Some(style);
+        self
+    }
+}
+
+impl<'a> Widget for Canvas<'a> {
+    fn draw_on(&self, ctx: &mut DrawingContext) {
+        let size = ctx.size();
+        if size.0 == 0 || size.1 == 0 {
+            return;
+        }
+
+        let style = ctx.themed(self.style, roles::CANVAS);
+        let mut painter = Painter::new(size, self.marker, self.x_bounds, self.y_bounds);
+        (self.paint)(&mut painter);
+        painter.draw_on(ctx, style);
+    }
+}
@@ -0,0 +1,760 @@
+use rustty::{Attr, Pos};
+use std::cmp::min;
+use super::{Cell, Color, DrawingContext, LineStyle, Style, Widget};
+use super::canvas::{Marker, Painter};
+use super::progress::{FULL_BLOCK, VERTICAL_EIGHTHS};
+use super::theme::{attr_from_bits, roles};
+
+/// Colors given to datasets without one of their own, in turn.
+const PALETTE: [Color; 6] =
+    [Color::Cyan, Color::Yellow, Color::Magenta, Color::Green, Color::Red, Color::Blue];
+
+/// The block for the row `row` cells above the bottom of a bar `eighths` high.
+#[inline]
+fn bar_char(eighths: usize, row: usize) -> char {
+    match eighths.saturating_sub(row * 8) {
+        0 => ' ',
+        n if n >= 8 => FULL_BLOCK,
+        n => VERTICAL_EIGHTHS[n - 1],
+    }
+}
+
+/// Height of `value` in eighths of a cell, out of `rows` cells for `max`.
+#[inline]
+fn bar_eighths(value: f64, max: f64, rows: usize) -> usize {
+    if max <= 0.0 || value.is_nan() {
+        return 0;
+    }
+    ((value / max).clamp(0.0, 1.0) * (rows * 8) as f64).round() as usize
+}
+
+/// Draws a bar from the bottom `bottom` up to `rows` cells high in column `x`.
+fn draw_bar(ctx: &mut DrawingContext,
+            x: usize,
+            bottom: usize,
+            rows: usize,
+            eighths: usize,
+            style: Style) {
+    for row in 0..min(rows, bottom + 1) {
+        let ch = bar_char(eighths, row);
+        ctx.set_cell((x, bottom - row), Cell::new(ch, style.0, style.1, style.2));
+    }
+}
+
+/// Shortest readable form of `value`, without decimals for whole numbers.
+pub(crate) fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        format!("{:.1}", value)
+    }
+}
+
+#[inline]
+fn reversed(style: Style) -> Style {
+    (style.0, style.1, attr_from_bits(style.2 as u8 | Attr::Reverse as u8))
+}
+
+/// A compact graph of the most recent values of a series, one column per value.
+pub struct Sparkline<'a> {
+    data: &'a [f64],
+    max: Option<f64>,
+    style: Option<Style>,
+}
+
+impl<'a> Sparkline<'a> {
+    /// The last values of `data` that fit are shown, the newest on the right.
+    pub fn new(data: &'a [f64]) -> Sparkline<'a> {
+        Sparkline {
+            data,
+            max: None,
+            style: None,
+        }
+    }
+
+    /// Value of a full height column. Defaults to the largest value shown.
+    #[inline]
+    pub fn max(mut self, max: f64) -> Sparkline<'a> {
+        self.max = Some(max);
+        self
+    }
+
+    #[inline]
+    pub fn style(mut self, style: Style) -> Sparkline<'a> {
+        self.style = Some(style);
+        self
+    }
+}
+
+impl<'a> Widget for Sparkline<'a> {
+    fn draw_on(&self, ctx: &mut DrawingContext) {
+        let (cols, rows) = ctx.size();
+        if cols == 0 || rows == 0 {
+            return;
+        }
+
+        let style = ctx.themed(self.style, roles::CHART);
+        let shown = &self.data[self.data.len().saturating_sub(cols)..];
+        let max = self.max.unwrap_or_else(|| shown.iter().cloned().fold(0.0, f64::max));
+
+        let start = cols - shown.len();
+
+        for x in 0..cols {
+            let value = x.checked_sub(start).and_then(|i| shown.get(i));
+            let eighths = value.map_or(0, |&v| bar_eighths(v, max, rows));
+            draw_bar(ctx, x, rows - 1, rows, eighths, style);
+        }
+    }
+}
+
+/// A single bar of a `BarChart`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bar<'a> {
+    value: f64,
+    label: &'a str,
+    style: Option<Style>,
+}
+
+impl<'a> Bar<'a> {
+    pub fn new(value: f64) -> Bar<'a> {
+        Bar {
+            value,
+            label: "",
+            style: None,
+        }
+    }
+
+    /// Text below the bar, cut to its width.
+    #[inline]
+    pub fn label(mut self, label: &'a str) -> Bar<'a> {
+        assert!(label.is_ascii());
+        self.label = label;
+        self
+    }
+
+    /// Style of the bar, drawn in the foreground color.
+    #[inline]
+    pub fn style(mut self, style: Style) -> Bar<'a> {
+        self.style = Some(style);
+        self
+    }
+}
+
+/// Bars drawn next to each other with a common label below them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BarGroup<'a> {
+    label: &'a str,
+    bars: Vec<Bar<'a>>,
+}
+
+impl<'a> BarGroup<'a> {
+    pub fn new(label: &'a str, bars: Vec<Bar<'a>>) -> BarGroup<'a> {
+        assert!(label.is_ascii());
+        BarGroup { label, bars }
+    }
+}
+
+/// Vertical bars in labeled groups, with a resolution of an eighth of a cell.
+///
+/// Groups that do not fit the width are left out. The value of a bar is shown at its bottom
+/// when it fits the width of the bar.
+pub struct BarChart<'a> {
+    groups: Vec<BarGroup<'a>>,
+    bar_width: usize,
+    bar_gap: usize,
+    group_gap: usize,
+    max: Option<f64>,
+    values: bool,
+
+    bar_style: Option<Style>,
+    label_style: Option<Style>,
+}
+
+impl<'a> BarChart<'a> {
+    pub fn new(groups: Vec<BarGroup<'a>>) -> BarChart<'a> {
+        BarChart {
+            groups,
+            bar_width: 3,
+            bar_gap: 1,
+            group_gap: 2,
+            max: None,
+            values: true,
+            bar_style: None,
+            label_style: None,
+        }
+    }
+
+    #[inline]
+    pub fn bar_width(mut self, width: usize) -> BarChart<'a> {
+        self.bar_width = width.max(1);
+        self
+    }
+
+    /// Columns between the bars of a group.
+    #[inline]
+    pub fn bar_gap(mut self, gap: usize) -> BarChart<'a> {
+        self.bar_gap = gap;
+        self
+    }
+
+    /// Columns between groups.
+    #[inline]
+    pub fn group_gap(mut self, gap: usize) -> BarChart<'a> {
+        self.group_gap = gap;
+        self
+    }
+
+    /// Value of a full height bar. Defaults to the largest value.
+    #[inline]
+    pub fn max(mut self, max: f64) -> BarChart<'a> {
+        self.max = Some(max);
+        self
+    }
+
+    /// Show the values of the bars.
+    #[inline]
+    pub fn values(mut self, values: bool) -> BarChart<'a> {
+        self.values = values;
+        self
+    }
+
+    /// Style of bars without one of their own.
+    #[inline]
+    pub fn bar_style(mut self, style: Style) -> BarChart<'a> {
+        self.bar_style = Some(style);
+        self
+    }
+
+    #[inline]
+    pub fn label_style(mut self, style: Style) -> BarChart<'a> {
+        self.label_style = Some(style);
+        self
+    }
+}
+
+impl<'a> Widget for BarChart<'a> {
+    fn draw_on(&self, ctx: &mut DrawingContext) {
+        let (cols, rows) = ctx.size();
+        let bars = self.groups.iter().flat_map(|g| g.bars.iter());
+        let bar_labels = bars.clone().any(|b| !b.label.is_empty());
+        let group_labels = self.groups.iter().any(|g| !g.label.is_empty());
+        let label_rows = bar_labels as usize + group_labels as usize;
+
+        if cols == 0 || rows <= label_rows {
+            return;
+        }
+
+        let bar_rows = rows - label_rows;
+        let bar_style = ctx.themed(self.bar_style, roles::CHART_BAR);
+        let label_style = ctx.themed(self.label_style, roles::CHART_LABEL);
+        let max = self.max.unwrap_or_else(|| bars.map(|b| b.value).fold(0.0, f64::max));
+        let width = self.bar_width;
+
+        let mut x = 0;
+        for group in &self.groups {
+            let n = group.bars.len();
+            let group_width = n * width + n.saturating_sub(1) * self.bar_gap;
+            if n == 0 || x + group_width > cols {
+                break;
+            }
+
+            for (i, bar) in group.bars.iter().enumerate() {
+                let left = x + i * (width + self.bar_gap);
+                let style = bar.style.unwrap_or(bar_style);
+                let eighths = bar_eighths(bar.value, max, bar_rows);
+
+                for col in left..left + width {
+                    draw_bar(ctx, col, bar_rows - 1, bar_rows, eighths, style);
+                }
+
+                let value = format_value(bar.value);
+                if self.values && eighths >= 8 && value.len() <= width {
+                    let offset = (width - value.len()) / 2;
+                    ctx.text((left + offset, bar_rows - 1), &value, reversed(style));
+                }
+
+                if bar_labels {
+                    let label = &bar.label[..min(bar.label.len(), width)];
+                    let offset = (width - label.len()) / 2;
+                    ctx.text((left + offset, bar_rows), label, label_style);
+                }
+            }
+
+            if group_labels {
+                let label = &group.label[..min(group.label.len(), group_width)];
+                let offset = (group_width - label.len()) / 2;
+                ctx.text((x + offset, rows - 1), label, label_style);
+            }
+
+            x += group_width + self.group_gap;
+        }
+    }
+}
+
+/// Distribution of raw samples, counted into bins of equal width and drawn as bars.
+///
+/// The range of the samples is shown below the bars. There is at most one bin per column.
+pub struct Histogram<'a> {
+    samples: &'a [f64],
+    bins: Option<usize>,
+    range: Option<(f64, f64)>,
+    values: bool,
+
+    bar_style: Option<Style>,
+    label_style: Option<Style>,
+}
+
+impl<'a> Histogram<'a> {
+    pub fn new(samples: &'a [f64]) -> Histogram<'a> {
+        Histogram {
+            samples,
+            bins: None,
+            range: None,
+            values: false,
+            bar_style: None,
+            label_style: None,
+        }
+    }
+
+    /// Number of bins. Defaults to Sturges' rule, `log2(n) + 1` for `n` samples.
+    #[inline]
+    pub fn bins(mut self, bins: usize) -> Histogram<'a> {
+        self.bins = Some(bins.max(1));
+        self
+    }
+
+    /// Range divided into bins, instead of that of the samples. Samples outside are ignored.
+    #[inline]
+    pub fn range(mut self, min: f64, max: f64) -> Histogram<'a> {
+        self.range = Some((min, max));
+        self
+    }
+
+    /// Show the counts of the bins.
+    #[inline]
+    pub fn values(mut self, values: bool) -> Histogram<'a> {
+        self.values = values;
+        self
+    }
+
+    #[inline]
+    pub fn bar_style(mut self, style: Style) -> Histogram<'a> {
+        self.bar_style = Some(style);
+        self
+    }
+
+    #[inline]
+    pub fn label_style(mut self, style: Style) -> Histogram<'a> {
+        self.label_style = Some(style);
+        self
+    }
+
+    fn bounds(&self) -> (f64, f64) {
+        if let Some(range) = self.range {
+            return range;
+        }
+
+        let samples = self.samples.iter().filter(|v| v.is_finite());
+        let (lo, hi) = samples.fold((f64::INFINITY, f64::NEG_INFINITY),
+                                    |(lo, hi), &v| (lo.min(v), hi.max(v)));
+        if lo <= hi { (lo, hi) } else { (0.0, 1.0) }
+    }
+
+    /// The number of samples in each of `bins` bins, with the range they cover.
+    pub fn counts(&self, bins: usize) -> (Vec<usize>, (f64, f64)) {
+        let (lo, hi) = self.bounds();
+        let mut counts = vec![0; bins.max(1)];
+        let last = counts.len() - 1;
+
+        for &sample in self.samples {
+            if !(sample >= lo && sample <= hi) {
+                continue;
+            }
+            let bin = if hi > lo {
+                ((sample - lo) / (hi - lo) * counts.len() as f64) as usize
+            } else {
+                0
+            };
+            // the largest sample belongs to the last bin
+            counts[min(bin, last)] += 1;
+        }
+        (counts, (lo, hi))
+    }
+}
+
+impl<'a> Widget for Histogram<'a> {
+    fn draw_on(&self, ctx: &mut DrawingContext) {
+        let (cols, rows) = ctx.size();
+        if cols == 0 || rows < 2 {
+            return;
+        }
+
+        let sturges = (self.samples.len().max(1) as f64).log2().ceil() as usize + 1;
+        let bins = min(self.bins.unwrap_or(sturges), cols);
+        let (counts, (lo, hi)) = self.counts(bins);
+
+        let bars = counts.iter().map(|&n| Bar::new(n as f64)).collect();
+        let mut chart = BarChart::new(vec![BarGroup::new("", bars)])
+            .bar_width(cols / bins)
+            .bar_gap(0)
+            .values(self.values);
+        if let Some(style) = self.bar_style {
+            chart = chart.bar_style(style);
+        }
+
+        ctx.save();
+        ctx.clip((cols, rows - 1));
+        chart.draw_on(ctx);
+        ctx.restore();
+
+        let label_style = ctx.themed(self.label_style, roles::CHART_LABEL);
+        let width = bins * (cols / bins);
+        let (low, high) = (format_value(lo), format_value(hi));
+        ctx.text((0, rows - 1), &low[..min(low.len(), width)], label_style);
+        if low.len() + high.len() < width {
+            ctx.text((width - high.len(), rows - 1), &high, label_style);
+        }
+    }
+}
+
+/// How the points of a `Dataset` are drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GraphType {
+    /// Points connected by lines.
+    #[default]
+    Line,
+    /// Points only.
+    Scatter,
+}
+
+/// A named series of `(x, y)` points of a `LineChart`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dataset<'a> {
+    name: &'a str,
+    data: &'a [(f64, f64)],
+    color: Option<Color>,
+    graph: GraphType,
+}
+
+impl<'a> Dataset<'a> {
+    pub fn new(name: &'a str, data: &'a [(f64, f64)]) -> Dataset<'a> {
+        assert!(name.is_ascii());
+        Dataset {
+            name,
+            data,
+            color: None,
+            graph: GraphType::Line,
+        }
+    }
+
+    /// Defaults to a color of its own, different for the first six datasets.
+    #[inline]
+    pub fn color(mut self, color: Color) -> Dataset<'a> {
+        self.color = Some(color);
+        self
+    }
+
+    #[inline]
+    pub fn graph(mut self, graph: GraphType) -> Dataset<'a> {
+        self.graph = graph;
+        self
+    }
+}
+
+/// An axis of a `LineChart`, scaled to the data unless bounds are set.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Axis<'a> {
+    title: &'a str,
+    bounds: Option<(f64, f64)>,
+    ticks: usize,
+}
+
+impl<'a> Default for Axis<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Rounds `value` to the closest of 1, 2 or 5 times a power of ten.
+fn nice(value: f64) -> f64 {
+    let exp = value.log10().floor();
+    let base = 10f64.powf(exp);
+    let fraction = value / base;
+
+    let nice = if fraction < 1.5 {
+        1.0
+    } else if fraction < 3.0 {
+        2.0
+    } else if fraction < 7.0 {
+        5.0
+    } else {
+        10.0
+    };
+    nice * base
+}
+
+impl<'a> Axis<'a> {
+    pub fn new() -> Axis<'a> {
+        Axis {
+            title: "",
+            bounds: None,
+            ticks: 5,
+        }
+    }
+
+    #[inline]
+    pub fn title(mut self, title: &'a str) -> Axis<'a> {
+        assert!(title.is_ascii());
+        self.title = title;
+        self
+    }
+
+    /// Fixes the range of the axis instead of fitting it to the data.
+    #[inline]
+    pub fn bounds(mut self, min: f64, max: f64) -> Axis<'a> {
+        self.bounds = Some((min, max));
+        self
+    }
+
+    /// Number of labeled ticks, at least 2.
+    #[inline]
+    pub fn ticks(mut self, ticks: usize) -> Axis<'a> {
+        self.ticks = ticks.max(2);
+        self
+    }
+
+    /// Bounds of the axis and its tick labels, for data between `lo` and `hi`.
+    fn scale(&self, lo: f64, hi: f64) -> ((f64, f64), Vec<(f64, String)>) {
+        let steps = (self.ticks - 1) as f64;
+        let (bounds, step) = match self.bounds {
+            Some(bounds) => (bounds, (bounds.1 - bounds.0) / steps),
+            None => {
+                let (lo, hi) = if hi > lo { (lo, hi) } else { (lo - 1.0, lo + 1.0) };
+                let step = nice((hi - lo) / steps);
+                (((lo / step).floor() * step, (hi / step).ceil() * step), step)
+            }
+        };
+
+        let decimals = if step.abs() > 0.0 && step.is_finite() {
+            (-step.abs().log10().floor()).max(0.0) as usize
+        } else {
+            0
+        };
+        let count = if self.bounds.is_some() {
+            self.ticks
+        } else {
+            ((bounds.1 - bounds.0) / step).round() as usize + 1
+        };
+
+        let ticks = (0..count)
+            .map(|i| {
+                let value = bounds.0 + step * i as f64;
+                (value, format!("{:.*}", decimals, value))
+            })
+            .collect();
+        (bounds, ticks)
+    }
+}
+
+/// Datasets plotted against two axes with tick labels, drawn with sub-cell precision.
+///
+/// Axes without bounds are fitted to the data and extended to round tick values. A legend
+/// listing the datasets is shown in the top right corner if it fits.
+pub struct LineChart<'a> {
+    datasets: Vec<Dataset<'a>>,
+    x_axis: Axis<'a>,
+    y_axis: Axis<'a>,
+    marker: Marker,
+    legend: bool,
+
+    style: Option<Style>,
+    axis_style: Option<Style>,
+}
+
+impl<'a> LineChart<'a> {
+    pub fn new(datasets: Vec<Dataset<'a>>) -> LineChart<'a> {
+        LineChart {
+            datasets,
+            x_axis: Axis::new(),
+            y_axis: Axis::new(),
+            marker: Marker::Braille,
+            legend: true,
+            style: None,
+            axis_style: None,
+        }
+    }
+
+    #[inline]
+    pub fn x_axis(mut self, axis: Axis<'a>) -> LineChart<'a> {
+        self.x_axis = axis;
+        self
+    }
+
+    #[inline]
+    pub fn y_axis(mut self, axis: Axis<'a>) -> LineChart<'a> {
+        self.y_axis = axis;
+        self
+    }
+
+    #[inline]
+    pub fn marker(mut self, marker: Marker) -> LineChart<'a> {
+        self.marker = marker;
+        self
+    }
+
+    #[inline]
+    pub fn legend(mut self, legend: bool) -> LineChart<'a> {
+        self.legend = legend;
+        self
+    }
+
+    /// Style of the plot area.
+    #[inline]
+    pub fn style(mut self, style: Style) -> LineChart<'a> {
+        self.style = Some(style);
+        self
+    }
+
+    /// Style of the axes, their labels and the legend frame.
+    #[inline]
+    pub fn axis_style(mut self, style: Style) -> LineChart<'a> {
+        self.axis_style = Some(style);
+        self
+    }
+
+    #[inline]
+    fn color(&self, idx: usize) -> Color {
+        self.datasets[idx].color.unwrap_or(PALETTE[idx % PALETTE.len()])
+    }
+
+    /// Smallest and largest coordinate of all points, picked by `coord`.
+    fn range<F: Fn(&(f64, f64)) -> f64>(&self, coord: F) -> (f64, f64) {
+        let values = self.datasets
+            .iter()
+            .flat_map(|d| d.data.iter().map(&coord))
+            .filter(|v| v.is_finite());
+        let (lo, hi) = values.fold((f64::INFINITY, f64::NEG_INFINITY),
+                                   |(lo, hi), v| (lo.min(v), hi.max(v)));
+
+        if lo <= hi { (lo, hi) } else { (0.0, 1.0) }
+    }
+
+    fn draw_legend(&self, ctx: &mut DrawingContext, top_right: Pos, style: Style) {
+        let names = self.datasets.iter().filter(|d| !d.name.is_empty()).count();
+        let width = self.datasets.iter().map(|d| d.name.len()).max().unwrap_or(0) + 4;
+        let height = names + 2;
+        if names == 0 || width > top_right.0 || top_right.1 + height > ctx.size().1 {
+            return;
+        }
+
+        let pos = (top_right.0 - width, top_right.1);
+        let blank = Cell::new(' ', style.0, style.1, style.2);
+        ctx.fill_rect(pos, (width, height), blank);
+        ctx.rect(pos, (width, height), LineStyle::Light, style);
+
+        let entries = self.datasets.iter().enumerate().filter(|&(_, d)| !d.name.is_empty());
+        for (row, (idx, dataset)) in entries.enumerate() {
+            let y = pos.1 + 1 + row;
+            let ch = match dataset.graph {
+                GraphType::Line => '─',
+                GraphType::Scatter => '•',
+            };
+            ctx.set_cell((pos.0 + 1, y), Cell::new(ch, self.color(idx), style.1, style.2));
+            ctx.text((pos.0 + 3, y), dataset.name, style);
+        }
+    }
+}
+
+impl<'a> Widget for LineChart<'a> {
+    fn draw_on(&self, ctx: &mut DrawingContext) {
+        let (cols, rows) = ctx.size();
+        let style = ctx.themed(self.style, roles::CHART);
+        let axis_style = ctx.themed(self.axis_style, roles::CHART_AXIS);
+        let label_style = ctx.themed(None, roles::CHART_LABEL);
+
+        let (x_lo, x_hi) = self.range(|p| p.0);
+        let (y_lo, y_hi) = self.range(|p| p.1);
+        let (x_bounds, x_ticks) = self.x_axis.scale(x_lo, x_hi);
+        let (y_bounds, y_ticks) = self.y_axis.scale(y_lo, y_hi);
+
+        // rows from the top: y title, plot, x axis, x labels, x title
+        let top = !self.y_axis.title.is_empty() as usize;
+        let bottom_rows = 2 + !self.x_axis.title.is_empty() as usize;
+        let label_width = y_ticks.iter().map(|t| t.1.len()).max().unwrap_or(0);
+        if rows < top + bottom_rows + 1 || cols < label_width + 2 {
+            return;
+        }
+
+        let axis_x = label_width;
+        let axis_y = rows - bottom_rows;
+        let plot = (cols - axis_x - 1, axis_y - top);
+
+        ctx.text((0, 0), &self.y_axis.title[..min(self.y_axis.title.len(), cols)], label_style);
+        if !self.x_axis.title.is_empty() {
+            let title = &self.x_axis.title[..min(self.x_axis.title.len(), cols)];
+            ctx.text((cols - title.len(), rows - 1), title, label_style);
+        }
+
+        ctx.vline((axis_x, top), plot.1, LineStyle::Light, axis_style);
+        ctx.hline((axis_x + 1, axis_y), plot.0, LineStyle::Light, axis_style);
+        ctx.set_cell((axis_x, axis_y), Cell::new('└', axis_style.0, axis_style.1, axis_style.2));
+
+        // fraction of an axis to the cell offset along it
+        let offset = |value: f64, bounds: (f64, f64), len: usize| {
+            let span = bounds.1 - bounds.0;
+            let fraction = if span != 0.0 { (value - bounds.0) / span } else { 0.0 };
+            (fraction.clamp(0.0, 1.0) * (len - 1) as f64).round() as usize
+        };
+
+        let mut last_row = None;
+        for &(value, ref label) in &y_ticks {
+            let y = axis_y - 1 - offset(value, y_bounds, plot.1);
+            if last_row == Some(y) {
+                continue;
+            }
+            last_row = Some(y);
+            ctx.text((label_width - label.len(), y), label, axis_style);
+            ctx.set_cell((axis_x, y), Cell::new('┤', axis_style.0, axis_style.1, axis_style.2));
+        }
+
+        let mut free = 0;
+        for &(value, ref label) in &x_ticks {
+            let x = axis_x + 1 + offset(value, x_bounds, plot.0);
+            let start = min(x.saturating_sub(label.len() / 2), cols.saturating_sub(label.len()));
+            ctx.set_cell((x, axis_y), Cell::new('┬', axis_style.0, axis_style.1, axis_style.2));
+            if start >= free && start + label.len() <= cols {
+                ctx.text((start, axis_y + 1), label, axis_style);
+                free = start + label.len() + 1;
+            }
+        }
+
+        let mut painter = Painter::new(plot, self.marker, x_bounds, y_bounds);
+        for (idx, dataset) in self.datasets.iter().enumerate() {
+            let color = self.color(idx);
+            match dataset.graph {
+                GraphType::Line => {
+                    for pair in dataset.data.windows(2) {
+                        painter.line(pair[0], pair[1], color);
+                    }
+                    if let [(x, y)] = *dataset.data {
+                        painter.point(x, y, color);
+                    }
+                }
+                GraphType::Scatter => {
+                    for &(x, y) in dataset.data {
+                        painter.point(x, y, color);
+                    }
+                }
+            }
+        }
+
+        ctx.save();
+        ctx.translate((axis_x + 1, top));
+        ctx.clip(plot);
+        painter.draw_on(ctx, style);
+        if self.legend {
+            self.draw_legend(ctx, (plot.0, 0), axis_style);
+        }
+        ctx.restore();
+    }
+}
@@ -0,0 +1,193 @@
+use rustty;
+use std::{cmp, env};
+use term::terminfo::TermInfo;
+
+/// A terminal color.
+///
+/// Besides the eight basic colors, colors of the 256-color palette and RGB colors can be
+/// used. Colors the terminal does not support are replaced by the nearest one it does, see
+/// `ColorSupport`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Color {
+    Default,
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    /// Color of the 256-color palette. The first 16 are the basic and bright colors.
+    Byte(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    /// Index of the color in the 256-color palette, `None` for the default color and RGB
+    /// colors.
+    pub fn index(&self) -> Option<u8> {
+        Some(match *self {
+            Color::Default | Color::Rgb(..) => return None,
+            Color::Black => 0,
+            Color::Red => 1,
+            Color::Green => 2,
+            Color::Yellow => 3,
+            Color::Blue => 4,
+            Color::Magenta => 5,
+            Color::Cyan => 6,
+            Color::White => 7,
+            Color::Byte(b) => b,
+        })
+    }
+
+    /// Approximate RGB value, using the colors of xterm for the palette.
+    pub fn rgb(&self) -> Option<(u8, u8, u8)> {
+        match *self {
+            Color::Rgb(r, g, b) => Some((r, g, b)),
+            _ => self.index().map(palette_rgb),
+        }
+    }
+
+    /// The nearest color available with `support`.
+    pub fn downsample(self, support: ColorSupport) -> Color {
+        match (support, self) {
+            (_, Color::Default) => self,
+            (ColorSupport::Mono, _) => Color::Default,
+            (ColorSupport::TrueColor, _) => self,
+            (ColorSupport::Palette256, Color::Rgb(r, g, b)) => {
+                Color::Byte(nearest_256((r, g, b)))
+            }
+            (ColorSupport::Palette256, _) => self,
+            (ColorSupport::Ansi16, _) => {
+                match self.index() {
+                    Some(n) if n < 16 => self,
+                    _ => Color::Byte(nearest_16(self.rgb().unwrap())),
+                }
+            }
+        }
+    }
+}
+
+impl From<rustty::Color> for Color {
+    fn from(color: rustty::Color) -> Color {
+        match color {
+            rustty::Color::Default => Color::Default,
+            rustty::Color::Byte(b) => Color::Byte(b),
+            c => Color::Byte(c.as_byte()),
+        }
+    }
+}
+
+/// Colors a terminal is able to display.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorSupport {
+    /// No colors, only attributes.
+    Mono,
+    /// The basic and bright colors.
+    Ansi16,
+    /// The 256-color palette.
+    Palette256,
+    /// RGB colors.
+    TrueColor,
+}
+
+impl ColorSupport {
+    /// Detects the colors supported by the terminal from the environment.
+    ///
+    /// `NO_COLOR` disables colors and `COLORTERM` announces RGB colors. Otherwise, the number
+    /// of colors is looked up in the terminfo entry for `TERM`, or guessed from its name.
+    pub fn detect() -> ColorSupport {
+        if env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+            return ColorSupport::Mono;
+        }
+
+        match env::var("COLORTERM") {
+            Ok(ref v) if v == "truecolor" || v == "24bit" => return ColorSupport::TrueColor,
+            _ => (),
+        }
+
+        let name = env::var("TERM").unwrap_or_default();
+        if name.is_empty() || name == "dumb" {
+            return ColorSupport::Mono;
+        }
+
+        match TermInfo::from_name(&name).map(|info| info.numbers.get("colors").cloned()) {
+            Ok(Some(n)) if n >= 256 => ColorSupport::Palette256,
+            Ok(Some(n)) if n >= 8 => ColorSupport::Ansi16,
+            Ok(_) => ColorSupport::Mono,
+            // the entry is missing, or in a format we do not understand
+            Err(_) if name.contains("256color") => ColorSupport::Palette256,
+            Err(_) => ColorSupport::Ansi16,
+        }
+    }
+}
+
+/// Levels of the color cube of the 256-color palette.
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// The basic and bright colors, as shown by xterm.
+const ANSI: [(u8, u8, u8); 16] = [(0, 0, 0),
+                                  (205, 0, 0),
+                                  (0, 205, 0),
+                                  (205, 205, 0),
+                                  (0, 0, 238),
+                                  (205, 0, 205),
+                                  (0, 205, 205),
+                                  (229, 229, 229),
+                                  (127, 127, 127),
+                                  (255, 0, 0),
+                                  (0, 255, 0),
+                                  (255, 255, 0),
+                                  (92, 92, 255),
+                                  (255, 0, 255),
+                                  (0, 255, 255),
+                                  (255, 255, 255)];
+
+fn palette_rgb(n: u8) -> (u8, u8, u8) {
+    match n {
+        0..=15 => ANSI[n as usize],
+        16..=231 => {
+            let n = n - 16;
+            (CUBE[(n / 36) as usize], CUBE[(n / 6 % 6) as usize], CUBE[(n % 6) as usize])
+        }
+        _ => {
+            let v = 8 + (n - 232) * 10;
+            (v, v, v)
+        }
+    }
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (i32::from(x) - i32::from(y)).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+/// Index of the cube level closest to `v`.
+fn cube_level(v: u8) -> u8 {
+    match v {
+        0..=47 => 0,
+        48..=114 => 1,
+        _ => (v - 35) / 40,
+    }
+}
+
+/// The nearest color of the cube and the gray ramp of the 256-color palette. The first 16
+/// colors are left out, as terminals often change them.
+fn nearest_256(rgb: (u8, u8, u8)) -> u8 {
+    let (r, g, b) = (cube_level(rgb.0), cube_level(rgb.1), cube_level(rgb.2));
+    let cube = 16 + 36 * r + 6 * g + b;
+
+    let avg = (u32::from(rgb.0) + u32::from(rgb.1) + u32::from(rgb.2)) / 3;
+    let gray = 232 + cmp::min(avg.saturating_sub(3) / 10, 23) as u8;
+
+    if distance(palette_rgb(gray), rgb) < distance(palette_rgb(cube), rgb) {
+        gray
+    } else {
+        cube
+    }
+}
+
+fn nearest_16(rgb: (u8, u8, u8)) -> u8 {
+    (0..16u8).min_by_key(|&n| distance(ANSI[n as usize], rgb)).unwrap()
+}
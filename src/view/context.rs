@@ -1,33 +1,159 @@
 use bresenham::Bresenham;
 
-use super::Style;
-use rustty::{Cell, Pos, Size, Terminal};
-use std::ascii::AsciiExt;
+use super::{Buffer, Cell, ColorSupport, Style};
+use super::boxes::{self, LineStyle, DOWN, LEFT, RIGHT, UP};
+use super::damage::Damage;
+use super::state::{Key, StateStore, WidgetId};
+use super::text::{Align, Line};
+use super::theme::{StyleSpec, Theme};
+use rustty::{Pos, Size};
+use std::any::Any;
+use std::mem;
 use std::ops::{Index, IndexMut};
 use std::cmp::min;
+use std::time::Duration;
 
 pub struct DrawingContext<'a> {
     states: Vec<DrawingContextState>,
-    term: &'a mut Terminal,
+    buffer: &'a mut Buffer,
+    elapsed: Duration,
+    damage: Option<&'a mut Damage>,
+    theme: &'a Theme,
+    colors: ColorSupport,
+    store: Option<&'a StateStore>,
+    id: WidgetId,
 }
 
 struct DrawingContextState {
     translation: Pos,
     size: Size,
+    // style containers pass down to the widgets drawn inside of them
+    inherited: StyleSpec,
 }
 
 impl<'a> DrawingContext<'a> {
-    pub fn new(term: &mut Terminal) -> DrawingContext {
+    pub fn new(buffer: &mut Buffer) -> DrawingContext<'_> {
         DrawingContext {
             states: vec![DrawingContextState {
                              translation: (0, 0),
-                             size: (term.cols(), term.rows()),
+                             size: buffer.size(),
+                             inherited: StyleSpec::new(),
                          }],
-            term: term,
+            buffer,
+            elapsed: Duration::from_secs(0),
+            damage: None,
+            theme: Theme::builtin(),
+            colors: ColorSupport::TrueColor,
+            store: None,
+            id: WidgetId::root(),
         }
 
     }
 
+    /// Creates a context recording all cells written into `damage`.
+    pub(crate) fn with_damage(buffer: &'a mut Buffer,
+                              damage: &'a mut Damage)
+                              -> DrawingContext<'a> {
+        let mut ctx = DrawingContext::new(buffer);
+        ctx.damage = Some(damage);
+        ctx
+    }
+
+    /// Time since the main loop started, used to drive animations.
+    #[inline(always)]
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    #[inline]
+    pub fn set_elapsed(&mut self, elapsed: Duration) {
+        self.elapsed = elapsed;
+    }
+
+    /// Theme used by widgets for styles not set on them explicitly.
+    #[inline(always)]
+    pub fn theme(&self) -> &'a Theme {
+        self.theme
+    }
+
+    #[inline]
+    pub fn set_theme(&mut self, theme: &'a Theme) {
+        self.theme = theme;
+    }
+
+    /// Colors the terminal is able to display, for widgets that pick colors themselves.
+    #[inline(always)]
+    pub fn colors(&self) -> ColorSupport {
+        self.colors
+    }
+
+    #[inline]
+    pub fn set_colors(&mut self, colors: ColorSupport) {
+        self.colors = colors;
+    }
+
+    /// Store widgets keep their state in between frames, if the application has one.
+    #[inline(always)]
+    pub fn store(&self) -> Option<&'a StateStore> {
+        self.store
+    }
+
+    #[inline]
+    pub fn set_store(&mut self, store: &'a StateStore) {
+        self.store = Some(store);
+    }
+
+    /// Identity of the widget being drawn, see `view::state`.
+    #[inline(always)]
+    pub fn widget_id(&self) -> &WidgetId {
+        &self.id
+    }
+
+    /// Runs `draw` for the child `key` of the widget being drawn. Containers call this with the
+    /// index of each child; an explicit name gives a widget an identity of its own.
+    pub fn keyed<K: Into<Key>, F: FnOnce(&mut DrawingContext)>(&mut self, key: K, draw: F) {
+        let child = self.id.child(key);
+        let parent = mem::replace(&mut self.id, child);
+        draw(self);
+        self.id = parent;
+    }
+
+    /// Runs `f` on the retained state of the widget being drawn. Without a store, `f` gets a
+    /// fresh default every time.
+    ///
+    /// Widgets drawn one after another without `keyed` in between share their identity, and
+    /// should keep their state in different types.
+    pub fn with_state<T, R, F>(&mut self, f: F) -> R
+        where T: Any + Default,
+              F: FnOnce(&mut T) -> R
+    {
+        match self.store {
+            Some(store) => store.with(&self.id, f),
+            None => f(&mut T::default()),
+        }
+    }
+
+    /// Returns `style` if set, otherwise the style of `role` in the current theme, on top of
+    /// the inherited style.
+    #[inline]
+    pub fn themed(&self, style: Option<Style>, role: &str) -> Style {
+        style.unwrap_or_else(|| self.theme.style_within(role, self.inherited()))
+    }
+
+    /// The style passed down by the containers the current widget is drawn in.
+    #[inline(always)]
+    pub fn inherited(&self) -> StyleSpec {
+        self.states.last().unwrap().inherited
+    }
+
+    /// Puts `spec` on top of the inherited style until the next `restore`. Containers call
+    /// this with their own style before drawing their children, e.g. to have a table take
+    /// the colors of the window it is in.
+    pub fn inherit(&mut self, spec: StyleSpec) {
+        let cur = self.states.last_mut().unwrap();
+        cur.inherited = cur.inherited.patch(spec);
+    }
+
     #[inline(always)]
     pub fn translation(&self) -> Pos {
         self.states.last().unwrap().translation
@@ -66,7 +192,7 @@ impl<'a> DrawingContext<'a> {
     #[inline(always)]
     pub fn transform(&self, mut p: Pos) -> Option<Pos> {
         // Size-check bounding box is not violated
-        if p.0 > self.size().0 || p.1 > self.size().0 {
+        if p.0 >= self.size().0 || p.1 >= self.size().1 {
             return None;
         }
 
@@ -75,7 +201,7 @@ impl<'a> DrawingContext<'a> {
         p.1 += self.translation().1;
 
         // check we're not drawing off-screen
-        if p.0 < self.term.cols() && p.1 < self.term.rows() {
+        if p.0 < self.buffer.cols() && p.1 < self.buffer.rows() {
             Some(p)
         } else {
             None
@@ -87,6 +213,7 @@ impl<'a> DrawingContext<'a> {
         let new_state = DrawingContextState {
             translation: self.translation(),
             size: self.size(),
+            inherited: self.inherited(),
         };
         self.states.push(new_state)
     }
@@ -101,32 +228,164 @@ impl<'a> DrawingContext<'a> {
 
     #[inline]
     pub fn get_cell(&self, p: Pos) -> Option<&Cell> {
-        self.transform(p).map(move |q| self.term.index(q))
+        self.transform(p).map(move |q| self.buffer.index(q))
     }
 
     #[inline]
     pub fn get_mut_cell(&mut self, p: Pos) -> Option<&mut Cell> {
-        self.transform(p).map(move |q| self.term.index_mut(q))
+        let q = self.transform(p)?;
+        if let Some(ref mut damage) = self.damage {
+            damage.mark(q);
+        }
+        Some(self.buffer.index_mut(q))
     }
 
     #[inline]
     pub fn set_cell(&mut self, p: Pos, cell: Cell) {
         if let Some(p) = self.transform(p) {
-            self.term[p] = cell;
+            if let Some(ref mut damage) = self.damage {
+                damage.mark(p);
+            }
+            self.buffer[p] = cell;
         }
     }
 
     pub fn fill(&mut self, cell: Cell) {
         let (w, h) = self.size();
         let (x0, y0) = self.translation();
+        let (x1, y1) = (min(self.buffer.cols(), w + x0), min(self.buffer.rows(), h + y0));
 
-        for x in x0..min(self.term.cols(), (w + x0)) {
-            for y in y0..min(self.term.rows(), (h + y0)) {
-                self.term[(x, y)] = cell;
+        if x1 <= x0 || y1 <= y0 {
+            return;
+        }
+
+        if let Some(ref mut damage) = self.damage {
+            damage.mark_region((x0, y0), (x1 - x0, y1 - y0));
+        }
+
+        for x in x0..x1 {
+            for y in y0..y1 {
+                self.buffer[(x, y)] = cell;
             }
         }
     }
 
+    /// Runs `draw` unless the current area still shows what it drew for the same `generation`
+    /// during the last frame.
+    ///
+    /// Skipping is safe as long as nothing else drew into the area in the meantime, which is
+    /// tracked automatically. Without damage tracking, `draw` is always run.
+    pub fn memoize<F: FnOnce(&mut DrawingContext)>(&mut self, generation: u64, draw: F) {
+        let region = (self.translation(), self.size());
+
+        let valid = match self.damage {
+            Some(ref damage) => damage.is_memo_valid(region, generation),
+            None => false,
+        };
+
+        if !valid {
+            draw(self);
+        } else if let Some(store) = self.store {
+            // the skipped widgets still show, so does their state
+            store.touch(&self.id);
+        }
+
+        if let Some(ref mut damage) = self.damage {
+            damage.store_memo(region, generation);
+        }
+    }
+
+    /// Fills the area of `size` at `pos`, clipped to the context.
+    pub fn fill_rect(&mut self, pos: Pos, size: Size, cell: Cell) {
+        if pos.0 >= self.size().0 || pos.1 >= self.size().1 {
+            return;
+        }
+
+        self.save();
+        self.translate(pos);
+        self.clip(size);
+        self.fill(cell);
+        self.restore();
+    }
+
+    /// Draws the outline of the area of `size` at `pos`, joining with lines already drawn.
+    pub fn rect(&mut self, pos: Pos, size: Size, line: LineStyle, style: Style) {
+        let (w, h) = size;
+        if w == 0 || h == 0 {
+            return;
+        } else if h == 1 {
+            return self.hline(pos, w, line, style);
+        } else if w == 1 {
+            return self.vline(pos, h, line, style);
+        }
+
+        let (left, top, right, bottom) = (pos.0, pos.1, pos.0 + w - 1, pos.1 + h - 1);
+        for x in left + 1..right {
+            self.join_cell((x, top), &[LEFT, RIGHT], &[], line, style);
+            self.join_cell((x, bottom), &[LEFT, RIGHT], &[], line, style);
+        }
+        for y in top + 1..bottom {
+            self.join_cell((left, y), &[UP, DOWN], &[], line, style);
+            self.join_cell((right, y), &[UP, DOWN], &[], line, style);
+        }
+
+        self.join_cell((left, top), &[RIGHT, DOWN], &[], line, style);
+        self.join_cell((right, top), &[LEFT, DOWN], &[], line, style);
+        self.join_cell((left, bottom), &[RIGHT, UP], &[], line, style);
+        self.join_cell((right, bottom), &[LEFT, UP], &[], line, style);
+    }
+
+    /// Draws a horizontal line of `len` cells to the right of `start`.
+    ///
+    /// Where it crosses other lines both are joined, and ends lying on a line connect to it
+    /// instead of sticking out, e.g. a line across a frame ends in `├` and `┤`.
+    pub fn hline(&mut self, start: Pos, len: usize, line: LineStyle, style: Style) {
+        for i in 0..len {
+            let (inward, outward): (&[usize], &[usize]) = match (i == 0, i + 1 == len) {
+                (true, true) => (&[], &[LEFT, RIGHT]),
+                (true, false) => (&[RIGHT], &[LEFT]),
+                (false, true) => (&[LEFT], &[RIGHT]),
+                (false, false) => (&[LEFT, RIGHT], &[]),
+            };
+            self.join_cell((start.0 + i, start.1), inward, outward, line, style);
+        }
+    }
+
+    /// Draws a vertical line of `len` cells down from `start`, joined like `hline`.
+    pub fn vline(&mut self, start: Pos, len: usize, line: LineStyle, style: Style) {
+        for i in 0..len {
+            let (inward, outward): (&[usize], &[usize]) = match (i == 0, i + 1 == len) {
+                (true, true) => (&[], &[UP, DOWN]),
+                (true, false) => (&[DOWN], &[UP]),
+                (false, true) => (&[UP], &[DOWN]),
+                (false, false) => (&[UP, DOWN], &[]),
+            };
+            self.join_cell((start.0, start.1 + i), inward, outward, line, style);
+        }
+    }
+
+    /// Adds the arms in `inward` to the line glyph at `p`, and those in `outward` too unless
+    /// the cell already shows a line.
+    fn join_cell(&mut self,
+                 p: Pos,
+                 inward: &[usize],
+                 outward: &[usize],
+                 line: LineStyle,
+                 style: Style) {
+        let existing = match self.get_cell(p) {
+            Some(cell) => cell.ch(),
+            None => return,
+        };
+
+        let ch = if boxes::is_line(existing) || outward.is_empty() {
+            boxes::join(existing, inward, line)
+        } else {
+            let dirs: Vec<usize> = inward.iter().chain(outward).cloned().collect();
+            boxes::join(existing, &dirs, line)
+        };
+        self.set_cell(p, Cell::new(ch, style.0, style.1, style.2));
+    }
+
     pub fn line(&mut self, start: Pos, end: Pos, cell: Cell) {
         let line = Bresenham::new((start.0 as isize, start.1 as isize),
                                   (end.0 as isize, end.1 as isize));
@@ -136,6 +395,13 @@ impl<'a> DrawingContext<'a> {
         }
     }
 
+    /// Draws connected lines through `points`, see `line`.
+    pub fn polyline(&mut self, points: &[Pos], cell: Cell) {
+        for pair in points.windows(2) {
+            self.line(pair[0], pair[1], cell);
+        }
+    }
+
     pub fn text<S: AsRef<str>>(&mut self, start: Pos, text: S, style: Style) {
         let s: &str = text.as_ref();
 
@@ -148,4 +414,43 @@ impl<'a> DrawingContext<'a> {
                           Cell::new(ch, style.0, style.1, style.2));
         }
     }
+
+    /// Draws the spans of `line` one after another, each put on top of `style`.
+    pub fn styled_text(&mut self, start: Pos, line: &Line, style: Style) {
+        let base = StyleSpec::from(style);
+        let mut x = start.0;
+
+        for span in &line.spans {
+            self.text((x, start.1), &span.content, base.patch(span.style).apply(style));
+            x += span.width();
+        }
+    }
+
+    /// Draws `lines` inside the area of `size` at `pos`, placed horizontally by `align.0` and
+    /// vertically by `align.1`. Lines too long for the area are cut off.
+    pub fn aligned_text(&mut self,
+                        pos: Pos,
+                        size: Size,
+                        lines: &[Line],
+                        align: (Align, Align),
+                        style: Style) {
+        if pos.0 >= self.size().0 || pos.1 >= self.size().1 {
+            return;
+        }
+
+        self.save();
+        self.translate(pos);
+        self.clip(size);
+
+        let (w, h) = self.size();
+        let top = align.1.offset(lines.len(), h);
+        for (y, line) in lines.iter().take(h - top).enumerate() {
+            let mut line = line.clone();
+            line.truncate(w);
+            let x = align.0.offset(line.width(), w);
+            self.styled_text((x, top + y), &line, style);
+        }
+
+        self.restore();
+    }
 }
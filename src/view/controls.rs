@@ -1,6 +1,78 @@
-use std::ascii::AsciiExt;
+use keymap::Keymap;
+use rustty::Event;
 use std::cmp::min;
-use super::{DEFAULT_STYLE, DrawingContext, Style, Widget};
+use super::{DrawingContext, Style, Widget};
+use super::theme::roles;
+
+/// Keys that activate a focused control.
+pub const ACTIVATE_KEYS: &[char] = &[' ', '\r'];
+
+/// A widget that changes state in response to keyboard input.
+///
+/// Controls do not hold on to their state; the application passes the current state in when
+/// building the control and receives the new state back as an action.
+pub trait Control {
+    type State;
+
+    /// Returns the state the control switches to when receiving `ev`, or `None` if the event is
+    /// ignored, e.g. because the control is not focused.
+    fn react(&self, ev: Event) -> Option<Self::State>;
+
+    /// Like `react`, but wraps the new state into an application action.
+    #[inline]
+    fn action_for<A, F>(&self, ev: Event, f: F) -> Option<A>
+        where F: FnOnce(Self::State) -> A
+    {
+        self.react(ev).map(f)
+    }
+}
+
+/// Tracks which of a fixed number of controls currently has keyboard focus.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FocusRing {
+    len: usize,
+    current: usize,
+}
+
+impl FocusRing {
+    pub fn new(len: usize) -> FocusRing {
+        FocusRing {
+            len,
+            current: 0,
+        }
+    }
+
+    #[inline]
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    #[inline]
+    pub fn is_focused(&self, idx: usize) -> bool {
+        self.len > 0 && self.current == idx
+    }
+
+    #[inline]
+    pub fn focus(&mut self, idx: usize) {
+        if idx < self.len {
+            self.current = idx;
+        }
+    }
+
+    #[inline]
+    pub fn next(&mut self) {
+        if self.len > 0 {
+            self.current = (self.current + 1) % self.len;
+        }
+    }
+
+    #[inline]
+    pub fn prev(&mut self) {
+        if self.len > 0 {
+            self.current = (self.current + self.len - 1) % self.len;
+        }
+    }
+}
 
 // FIXME: make other parts of framework use builder patterns too?
 pub struct IndicatorButton<'a> {
@@ -9,10 +81,15 @@ pub struct IndicatorButton<'a> {
     hotkey: &'a str,
     size: usize,
 
-    // FIXME: find pattern for style object, maybe using trait?
-    active_style: Style,
-    inactive_style: Style,
-    hotkey_style: Style,
+    active_style: Option<Style>,
+    inactive_style: Option<Style>,
+    hotkey_style: Option<Style>,
+}
+
+impl<'a> Default for IndicatorButton<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<'a> IndicatorButton<'a> {
@@ -23,9 +100,9 @@ impl<'a> IndicatorButton<'a> {
             text: "",
             hotkey: "",
             size: 12,
-            active_style: DEFAULT_STYLE,
-            inactive_style: DEFAULT_STYLE,
-            hotkey_style: DEFAULT_STYLE,
+            active_style: None,
+            inactive_style: None,
+            hotkey_style: None,
         }
     }
 
@@ -49,6 +126,15 @@ impl<'a> IndicatorButton<'a> {
         self
     }
 
+    /// Uses the keys bound to `action` in `keymap` as hotkey, if any.
+    #[inline]
+    pub fn hotkey_for<A: PartialEq>(self,
+                                    keymap: &'a Keymap<A>,
+                                    action: &A)
+                                    -> IndicatorButton<'a> {
+        self.hotkey(keymap.keys_for(action).unwrap_or(""))
+    }
+
     #[inline]
     pub fn size(mut self, size: usize) -> IndicatorButton<'a> {
         self.size = size;
@@ -57,19 +143,19 @@ impl<'a> IndicatorButton<'a> {
 
     #[inline]
     pub fn active_style(mut self, style: Style) -> IndicatorButton<'a> {
-        self.active_style = style;
+        self.active_style = Some(style);
         self
     }
 
     #[inline]
     pub fn inactive_style(mut self, style: Style) -> IndicatorButton<'a> {
-        self.inactive_style = style;
+        self.inactive_style = Some(style);
         self
     }
 
     #[inline]
     pub fn hotkey_style(mut self, style: Style) -> IndicatorButton<'a> {
-        self.hotkey_style = style;
+        self.hotkey_style = Some(style);
         self
     }
 }
@@ -79,15 +165,395 @@ impl<'a> Widget for IndicatorButton<'a> {
         let btn_offset = min(self.size, self.hotkey.len());
 
         // first, draw hotkey
-        ctx.text((0, 0), &self.hotkey[0..btn_offset], self.hotkey_style);
+        let hotkey_style = ctx.themed(self.hotkey_style, roles::BUTTON_HOTKEY);
+        ctx.text((0, 0), &self.hotkey[0..btn_offset], hotkey_style);
 
         // then draw button
+        let style = if self.enabled {
+            ctx.themed(self.active_style, roles::BUTTON_ACTIVE)
+        } else {
+            ctx.themed(self.inactive_style, roles::BUTTON)
+        };
         ctx.text((btn_offset, 0),
                  &self.text[0..min(self.size - btn_offset, self.text.len())],
-                 if self.enabled {
-                     self.active_style
-                 } else {
-                     self.inactive_style
-                 });
+                 style);
+    }
+}
+
+
+/// State of a `Checkbox`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CheckState {
+    #[default]
+    Unchecked,
+    Checked,
+    Indeterminate,
+}
+
+impl From<bool> for CheckState {
+    fn from(checked: bool) -> Self {
+        if checked {
+            CheckState::Checked
+        } else {
+            CheckState::Unchecked
+        }
+    }
+}
+
+pub struct Checkbox<'a> {
+    state: CheckState,
+    tristate: bool,
+    focused: bool,
+    text: &'a str,
+
+    style: Option<Style>,
+    focused_style: Option<Style>,
+}
+
+impl<'a> Default for Checkbox<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Checkbox<'a> {
+    #[inline]
+    pub fn new() -> Checkbox<'a> {
+        Checkbox {
+            state: CheckState::Unchecked,
+            tristate: false,
+            focused: false,
+            text: "",
+            style: None,
+            focused_style: None,
+        }
+    }
+
+    #[inline]
+    pub fn state(mut self, state: CheckState) -> Checkbox<'a> {
+        self.state = state;
+        self
+    }
+
+    /// Include `CheckState::Indeterminate` when cycling through states on activation.
+    #[inline]
+    pub fn tristate(mut self, tristate: bool) -> Checkbox<'a> {
+        self.tristate = tristate;
+        self
+    }
+
+    #[inline]
+    pub fn focused(mut self, focused: bool) -> Checkbox<'a> {
+        self.focused = focused;
+        self
+    }
+
+    #[inline]
+    pub fn text(mut self, text: &'a str) -> Checkbox<'a> {
+        assert!(text.is_ascii());
+        self.text = text;
+        self
+    }
+
+    #[inline]
+    pub fn style(mut self, style: Style) -> Checkbox<'a> {
+        self.style = Some(style);
+        self
+    }
+
+    #[inline]
+    pub fn focused_style(mut self, style: Style) -> Checkbox<'a> {
+        self.focused_style = Some(style);
+        self
+    }
+
+    /// Returns the state following the current one when the checkbox is activated.
+    pub fn next_state(&self) -> CheckState {
+        match self.state {
+            CheckState::Unchecked => CheckState::Checked,
+            CheckState::Checked if self.tristate => CheckState::Indeterminate,
+            CheckState::Checked | CheckState::Indeterminate => CheckState::Unchecked,
+        }
+    }
+}
+
+impl<'a> Control for Checkbox<'a> {
+    type State = CheckState;
+
+    fn react(&self, ev: Event) -> Option<CheckState> {
+        match ev {
+            Event::Key(ch) if self.focused && ACTIVATE_KEYS.contains(&ch) => {
+                Some(self.next_state())
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Widget for Checkbox<'a> {
+    fn draw_on(&self, ctx: &mut DrawingContext) {
+        let style = if self.focused {
+            ctx.themed(self.focused_style, roles::CHECKBOX_FOCUSED)
+        } else {
+            ctx.themed(self.style, roles::CHECKBOX)
+        };
+
+        let indicator = match self.state {
+            CheckState::Unchecked => "[ ] ",
+            CheckState::Checked => "[x] ",
+            CheckState::Indeterminate => "[-] ",
+        };
+
+        ctx.text((0, 0), indicator, style);
+        ctx.text((indicator.len(), 0), self.text, style);
+    }
+}
+
+/// A group of mutually exclusive options, drawn one per row.
+///
+/// While focused, `next_key` and `prev_key` move the selection; the digits `1` to `9` select an
+/// option directly.
+pub struct RadioGroup<'a> {
+    options: &'a [&'a str],
+    selected: Option<usize>,
+    focused: bool,
+    next_key: char,
+    prev_key: char,
+
+    style: Option<Style>,
+    selected_style: Option<Style>,
+    focused_style: Option<Style>,
+}
+
+impl<'a> RadioGroup<'a> {
+    #[inline]
+    pub fn new(options: &'a [&'a str]) -> RadioGroup<'a> {
+        assert!(options.iter().all(|opt| opt.is_ascii()));
+        RadioGroup {
+            options,
+            selected: None,
+            focused: false,
+            next_key: 'j',
+            prev_key: 'k',
+            style: None,
+            selected_style: None,
+            focused_style: None,
+        }
+    }
+
+    #[inline]
+    pub fn selected(mut self, selected: Option<usize>) -> RadioGroup<'a> {
+        self.selected = selected.filter(|&idx| idx < self.options.len());
+        self
+    }
+
+    #[inline]
+    pub fn focused(mut self, focused: bool) -> RadioGroup<'a> {
+        self.focused = focused;
+        self
+    }
+
+    #[inline]
+    pub fn keys(mut self, next_key: char, prev_key: char) -> RadioGroup<'a> {
+        self.next_key = next_key;
+        self.prev_key = prev_key;
+        self
+    }
+
+    #[inline]
+    pub fn style(mut self, style: Style) -> RadioGroup<'a> {
+        self.style = Some(style);
+        self
+    }
+
+    #[inline]
+    pub fn selected_style(mut self, style: Style) -> RadioGroup<'a> {
+        self.selected_style = Some(style);
+        self
+    }
+
+    /// Style of the selected option while the group is focused.
+    #[inline]
+    pub fn focused_style(mut self, style: Style) -> RadioGroup<'a> {
+        self.focused_style = Some(style);
+        self
+    }
+}
+
+impl<'a> Control for RadioGroup<'a> {
+    type State = usize;
+
+    fn react(&self, ev: Event) -> Option<usize> {
+        let num = self.options.len();
+
+        if !self.focused || num == 0 {
+            return None;
+        }
+
+        let Event::Key(ch) = ev;
+
+        let idx = if ch == self.next_key {
+            self.selected.map_or(0, |idx| (idx + 1) % num)
+        } else if ch == self.prev_key {
+            self.selected.map_or(num - 1, |idx| (idx + num - 1) % num)
+        } else {
+            match ch.to_digit(10) {
+                Some(d) if d >= 1 && (d as usize) <= num => d as usize - 1,
+                _ => return None,
+            }
+        };
+
+        if Some(idx) == self.selected {
+            None
+        } else {
+            Some(idx)
+        }
+    }
+}
+
+impl<'a> Widget for RadioGroup<'a> {
+    fn draw_on(&self, ctx: &mut DrawingContext) {
+        let rows = ctx.size().1;
+        let style = ctx.themed(self.style, roles::RADIO);
+        let selected_style = if self.focused {
+            ctx.themed(self.focused_style, roles::RADIO_FOCUSED)
+        } else {
+            ctx.themed(self.selected_style, roles::RADIO_SELECTED)
+        };
+
+        for (idx, opt) in self.options.iter().enumerate().take(rows) {
+            let (indicator, style) = if Some(idx) == self.selected {
+                ("(*) ", selected_style)
+            } else {
+                ("( ) ", style)
+            };
+
+            ctx.text((0, idx), indicator, style);
+            ctx.text((indicator.len(), idx), opt, style);
+        }
+    }
+}
+
+/// An on/off switch.
+pub struct Toggle<'a> {
+    on: bool,
+    focused: bool,
+    text: &'a str,
+    on_text: &'a str,
+    off_text: &'a str,
+
+    style: Option<Style>,
+    on_style: Option<Style>,
+    off_style: Option<Style>,
+    focused_style: Option<Style>,
+}
+
+impl<'a> Default for Toggle<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Toggle<'a> {
+    #[inline]
+    pub fn new() -> Toggle<'a> {
+        Toggle {
+            on: false,
+            focused: false,
+            text: "",
+            on_text: " ON",
+            off_text: "OFF",
+            style: None,
+            on_style: None,
+            off_style: None,
+            focused_style: None,
+        }
+    }
+
+    #[inline]
+    pub fn on(mut self, on: bool) -> Toggle<'a> {
+        self.on = on;
+        self
+    }
+
+    #[inline]
+    pub fn focused(mut self, focused: bool) -> Toggle<'a> {
+        self.focused = focused;
+        self
+    }
+
+    #[inline]
+    pub fn text(mut self, text: &'a str) -> Toggle<'a> {
+        assert!(text.is_ascii());
+        self.text = text;
+        self
+    }
+
+    /// Texts shown inside the switch for either state.
+    #[inline]
+    pub fn state_texts(mut self, on_text: &'a str, off_text: &'a str) -> Toggle<'a> {
+        assert!(on_text.is_ascii() && off_text.is_ascii());
+        self.on_text = on_text;
+        self.off_text = off_text;
+        self
+    }
+
+    #[inline]
+    pub fn style(mut self, style: Style) -> Toggle<'a> {
+        self.style = Some(style);
+        self
+    }
+
+    #[inline]
+    pub fn on_style(mut self, style: Style) -> Toggle<'a> {
+        self.on_style = Some(style);
+        self
+    }
+
+    #[inline]
+    pub fn off_style(mut self, style: Style) -> Toggle<'a> {
+        self.off_style = Some(style);
+        self
+    }
+
+    /// Style of the label while the toggle is focused.
+    #[inline]
+    pub fn focused_style(mut self, style: Style) -> Toggle<'a> {
+        self.focused_style = Some(style);
+        self
+    }
+}
+
+impl<'a> Control for Toggle<'a> {
+    type State = bool;
+
+    fn react(&self, ev: Event) -> Option<bool> {
+        match ev {
+            Event::Key(ch) if self.focused && ACTIVATE_KEYS.contains(&ch) => Some(!self.on),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Widget for Toggle<'a> {
+    fn draw_on(&self, ctx: &mut DrawingContext) {
+        let style = ctx.themed(self.style, roles::TOGGLE);
+        let (state_text, state_style) = if self.on {
+            (self.on_text, ctx.themed(self.on_style, roles::TOGGLE_ON))
+        } else {
+            (self.off_text, ctx.themed(self.off_style, roles::TOGGLE_OFF))
+        };
+
+        // switch first, so it lines up when several toggles are stacked
+        ctx.text((0, 0), "[", style);
+        ctx.text((1, 0), state_text, state_style);
+        ctx.text((1 + state_text.len(), 0), "] ", style);
+
+        let label_style = if self.focused {
+            ctx.themed(self.focused_style, roles::TOGGLE_FOCUSED)
+        } else {
+            style
+        };
+        ctx.text((3 + state_text.len(), 0), self.text, label_style);
     }
 }
@@ -0,0 +1,121 @@
+use rustty::{Pos, Size};
+use std::cmp::{max, min};
+use std::collections::HashMap;
+
+/// A rectangular screen region, as translation and size.
+type Region = (Pos, Size);
+
+#[inline]
+fn overlaps(a: &Region, b: &Region) -> bool {
+    let (((ax, ay), (aw, ah)), ((bx, by), (bw, bh))) = (*a, *b);
+    ax < bx + bw && bx < ax + aw && ay < by + bh && by < ay + ah
+}
+
+/// Records which cells were written during a frame.
+///
+/// The renderer only compares written cells against the previous frame. Memoized regions (see
+/// `DrawingContext::memoize`) whose content is known to be unchanged are not redrawn at all;
+/// their cells from the last frame are still in the back buffer.
+#[derive(Default)]
+pub struct Damage {
+    size: Size,
+    full: bool,
+
+    // per row, the first and last column written
+    spans: Vec<Option<(usize, usize)>>,
+
+    memos: HashMap<Region, u64>,
+    next_memos: Vec<(Region, u64)>,
+}
+
+impl Damage {
+    pub fn new() -> Damage {
+        Damage {
+            full: true,
+            ..Damage::default()
+        }
+    }
+
+    /// Prepares tracking for the next frame on a screen of `size`.
+    pub fn begin_frame(&mut self, size: Size) {
+        if size != self.size {
+            self.size = size;
+            self.invalidate();
+        }
+
+        self.spans.clear();
+        self.spans.resize(size.1, None);
+
+        // memos that survived the last frame may be reused in this one
+        self.memos = self.next_memos.drain(..).collect();
+    }
+
+    /// Forgets everything known about the screen contents, e.g. after it was cleared.
+    pub fn invalidate(&mut self) {
+        self.full = true;
+        self.memos.clear();
+        self.next_memos.clear();
+    }
+
+    /// Returns whether every cell must be compared, instead of only the written ones.
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.full
+    }
+
+    /// Called once the frame has been output.
+    #[inline]
+    pub fn end_frame(&mut self) {
+        self.full = false;
+    }
+
+    /// Columns written in row `y`, as an inclusive range.
+    #[inline]
+    pub fn span(&self, y: usize) -> Option<(usize, usize)> {
+        self.spans.get(y).and_then(|&s| s)
+    }
+
+    #[inline]
+    pub fn mark(&mut self, p: Pos) {
+        self.mark_region(p, (1, 1))
+    }
+
+    pub fn mark_region(&mut self, pos: Pos, size: Size) {
+        if size.0 == 0 || size.1 == 0 {
+            return;
+        }
+
+        let (x0, x1) = (pos.0, pos.0 + size.0 - 1);
+        for y in pos.1..min(pos.1 + size.1, self.spans.len()) {
+            self.spans[y] = Some(match self.spans[y] {
+                Some((l, r)) => (min(l, x0), max(r, x1)),
+                None => (x0, x1),
+            });
+        }
+
+        // drawing over a memoized region means it has to be redrawn next time
+        if !self.next_memos.is_empty() {
+            let written = (pos, size);
+            self.next_memos.retain(|(region, _)| !overlaps(region, &written));
+        }
+    }
+
+    /// Returns whether `region` still shows what was drawn for `generation` last frame.
+    pub fn is_memo_valid(&self, region: Region, generation: u64) -> bool {
+        if self.memos.get(&region) != Some(&generation) {
+            return false;
+        }
+
+        // something else drew into the region earlier during this frame
+        let ((x, y), (w, h)) = region;
+        !(y..min(y + h, self.spans.len()))
+            .any(|row| match self.spans[row] {
+                Some((l, r)) => l < x + w && r >= x,
+                None => false,
+            })
+    }
+
+    pub fn store_memo(&mut self, region: Region, generation: u64) {
+        self.next_memos.push((region, generation));
+    }
+}
@@ -1,19 +1,25 @@
-use rustty::Cell;
-use super::{DrawingContext, Widget};
+use super::{Cell, DrawingContext, Widget};
+use super::theme::roles;
 
 
+/// Fills the whole area, by default with the style of the `background` role.
+#[derive(Default)]
 pub struct Background {
-    bg_cell: Cell,
+    bg_cell: Option<Cell>,
 }
 
 impl Background {
     pub fn new(cell: Cell) -> Background {
-        Background { bg_cell: cell }
+        Background { bg_cell: Some(cell) }
     }
 }
 
 impl Widget for Background {
     fn draw_on(&self, ctx: &mut DrawingContext) {
-        ctx.fill(self.bg_cell)
+        let bg_cell = self.bg_cell.unwrap_or_else(|| {
+            let style = ctx.themed(None, roles::BACKGROUND);
+            Cell::new(' ', style.0, style.1, style.2)
+        });
+        ctx.fill(bg_cell)
     }
 }
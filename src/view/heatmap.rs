@@ -0,0 +1,204 @@
+use std::cmp::min;
+use super::{Cell, Color, ColorSupport, DrawingContext, Style, Widget};
+use super::chart::format_value;
+use super::theme::roles;
+
+/// Shades used instead of colors on terminals without them, from low to high.
+const SHADES: [char; 5] = [' ', '░', '▒', '▓', '█'];
+
+/// Colors for values between `0.0` and `1.0`, interpolated between stops.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+    stops: Vec<(f64, (u8, u8, u8))>,
+}
+
+impl Gradient {
+    /// A gradient through `stops` of a position between `0.0` and `1.0` and an RGB color,
+    /// sorted by position.
+    pub fn new(stops: Vec<(f64, (u8, u8, u8))>) -> Gradient {
+        assert!(!stops.is_empty());
+        Gradient { stops }
+    }
+
+    /// Black through red and yellow to white.
+    pub fn heat() -> Gradient {
+        Gradient::new(vec![(0.0, (0, 0, 0)),
+                           (0.35, (190, 30, 0)),
+                           (0.7, (255, 200, 0)),
+                           (1.0, (255, 255, 255))])
+    }
+
+    /// Dark blue through teal and green to yellow, readable for most color blind people.
+    pub fn viridis() -> Gradient {
+        Gradient::new(vec![(0.0, (68, 1, 84)),
+                           (0.25, (59, 82, 139)),
+                           (0.5, (33, 145, 140)),
+                           (0.75, (94, 201, 98)),
+                           (1.0, (253, 231, 37))])
+    }
+
+    pub fn grayscale() -> Gradient {
+        Gradient::new(vec![(0.0, (0, 0, 0)), (1.0, (255, 255, 255))])
+    }
+
+    /// The color at `t`, clamped to the range of the gradient.
+    pub fn at(&self, t: f64) -> Color {
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        let upper = self.stops.iter().position(|&(pos, _)| pos >= t);
+
+        let (r, g, b) = match upper {
+            Some(0) => self.stops[0].1,
+            None => self.stops[self.stops.len() - 1].1,
+            Some(i) => {
+                let (p0, c0) = self.stops[i - 1];
+                let (p1, c1) = self.stops[i];
+                let f = if p1 > p0 { (t - p0) / (p1 - p0) } else { 1.0 };
+                let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * f).round() as u8;
+                (mix(c0.0, c1.0), mix(c0.1, c1.1), mix(c0.2, c1.2))
+            }
+        };
+        Color::Rgb(r, g, b)
+    }
+}
+
+impl Default for Gradient {
+    fn default() -> Self {
+        Gradient::viridis()
+    }
+}
+
+/// A matrix of values drawn as colored cells, one row of cells per row of values.
+///
+/// Values are mapped onto the gradient from the smallest to the largest one, unless a range
+/// is set. Colors are reduced to those the terminal supports, and replaced by shades without
+/// colors. Values that are NaN are left blank.
+pub struct Heatmap<'a> {
+    rows: &'a [Vec<f64>],
+    gradient: Gradient,
+    range: Option<(f64, f64)>,
+    cell_width: usize,
+    legend: bool,
+
+    style: Option<Style>,
+    label_style: Option<Style>,
+}
+
+impl<'a> Heatmap<'a> {
+    pub fn new(rows: &'a [Vec<f64>]) -> Heatmap<'a> {
+        Heatmap {
+            rows,
+            gradient: Gradient::default(),
+            range: None,
+            cell_width: 2,
+            legend: true,
+            style: None,
+            label_style: None,
+        }
+    }
+
+    #[inline]
+    pub fn gradient(mut self, gradient: Gradient) -> Heatmap<'a> {
+        self.gradient = gradient;
+        self
+    }
+
+    /// Values mapped to both ends of the gradient, instead of the extremes of the data.
+    #[inline]
+    pub fn range(mut self, min: f64, max: f64) -> Heatmap<'a> {
+        self.range = Some((min, max));
+        self
+    }
+
+    /// Columns per value, 2 by default which makes cells roughly square.
+    #[inline]
+    pub fn cell_width(mut self, width: usize) -> Heatmap<'a> {
+        self.cell_width = width.max(1);
+        self
+    }
+
+    /// Show the gradient with the values of its ends below the matrix.
+    #[inline]
+    pub fn legend(mut self, legend: bool) -> Heatmap<'a> {
+        self.legend = legend;
+        self
+    }
+
+    #[inline]
+    pub fn style(mut self, style: Style) -> Heatmap<'a> {
+        self.style = Some(style);
+        self
+    }
+
+    #[inline]
+    pub fn label_style(mut self, style: Style) -> Heatmap<'a> {
+        self.label_style = Some(style);
+        self
+    }
+
+    fn bounds(&self) -> (f64, f64) {
+        if let Some(range) = self.range {
+            return range;
+        }
+
+        let values = self.rows.iter().flat_map(|r| r.iter()).filter(|v| v.is_finite());
+        let (lo, hi) = values.fold((f64::INFINITY, f64::NEG_INFINITY),
+                                   |(lo, hi), &v| (lo.min(v), hi.max(v)));
+        if lo <= hi { (lo, hi) } else { (0.0, 1.0) }
+    }
+
+    /// The cell showing the position `t` on the gradient.
+    fn cell(&self, t: f64, colors: ColorSupport, style: Style) -> Cell {
+        if t.is_nan() {
+            Cell::new(' ', style.0, style.1, style.2)
+        } else if colors == ColorSupport::Mono {
+            let shade = (t.clamp(0.0, 1.0) * (SHADES.len() - 1) as f64).round() as usize;
+            Cell::new(SHADES[shade], style.0, style.1, style.2)
+        } else {
+            let color = self.gradient.at(t).downsample(colors);
+            Cell::new(' ', style.0, color, style.2)
+        }
+    }
+}
+
+impl<'a> Widget for Heatmap<'a> {
+    fn draw_on(&self, ctx: &mut DrawingContext) {
+        let (cols, rows) = ctx.size();
+        if cols == 0 || rows == 0 {
+            return;
+        }
+
+        let style = ctx.themed(self.style, roles::HEATMAP);
+        let label_style = ctx.themed(self.label_style, roles::CHART_LABEL);
+        let colors = ctx.colors();
+        let (lo, hi) = self.bounds();
+        let fraction = |v: f64| if hi > lo { (v - lo) / (hi - lo) } else { 0.5 };
+
+        let legend = self.legend && rows > 1;
+        let map_rows = if legend { rows - 1 } else { rows };
+
+        for (y, values) in self.rows.iter().take(map_rows).enumerate() {
+            for (i, &value) in values.iter().enumerate() {
+                let cell = self.cell(fraction(value), colors, style);
+                for x in i * self.cell_width..min((i + 1) * self.cell_width, cols) {
+                    ctx.set_cell((x, y), cell);
+                }
+            }
+        }
+
+        if legend {
+            let y = min(self.rows.len(), map_rows);
+            let (low, high) = (format_value(lo), format_value(hi));
+            let bar = min(cols.saturating_sub(low.len() + high.len() + 2), 20);
+            if bar == 0 {
+                return;
+            }
+
+            ctx.text((0, y), &low, label_style);
+            for i in 0..bar {
+                let t = if bar > 1 { i as f64 / (bar - 1) as f64 } else { 0.5 };
+                ctx.set_cell((low.len() + 1 + i, y), self.cell(t, colors, style));
+            }
+            ctx.text((low.len() + bar + 2, y), &high, label_style);
+        }
+    }
+}
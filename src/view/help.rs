@@ -0,0 +1,82 @@
+use keymap::HelpEntry;
+use super::{Cell, DrawingContext, Style, Widget};
+use super::theme::roles;
+
+/// Overview of active key bindings, as listed by `keymap::help`.
+///
+/// Bindings are grouped by the layer they belong to, with the keys aligned in a column. Entries
+/// not fitting the height are cut off.
+pub struct KeyHelp<'a> {
+    entries: &'a [HelpEntry<'a>],
+    style: Option<Style>,
+    keys_style: Option<Style>,
+    heading_style: Option<Style>,
+}
+
+impl<'a> KeyHelp<'a> {
+    pub fn new(entries: &'a [HelpEntry<'a>]) -> KeyHelp<'a> {
+        KeyHelp {
+            entries,
+            style: None,
+            keys_style: None,
+            heading_style: None,
+        }
+    }
+
+    #[inline]
+    pub fn style(mut self, style: Style) -> KeyHelp<'a> {
+        self.style = Some(style);
+        self
+    }
+
+    #[inline]
+    pub fn keys_style(mut self, style: Style) -> KeyHelp<'a> {
+        self.keys_style = Some(style);
+        self
+    }
+
+    #[inline]
+    pub fn heading_style(mut self, style: Style) -> KeyHelp<'a> {
+        self.heading_style = Some(style);
+        self
+    }
+}
+
+impl<'a> Widget for KeyHelp<'a> {
+    fn draw_on(&self, ctx: &mut DrawingContext) {
+        let style = ctx.themed(self.style, roles::HELP);
+        let keys_style = ctx.themed(self.keys_style, roles::HELP_KEYS);
+        let heading_style = ctx.themed(self.heading_style, roles::HELP_HEADING);
+
+        ctx.fill(Cell::new(' ', style.0, style.1, style.2));
+
+        let height = ctx.size().1;
+        let keys_width = self.entries.iter().map(|e| e.keys.len()).max().unwrap_or(0);
+
+        let mut y = 0;
+        let mut layer = None;
+        for entry in self.entries {
+            if y >= height {
+                break;
+            }
+
+            if layer != Some(entry.layer) {
+                // separate groups by an empty line
+                if layer.is_some() {
+                    y += 1;
+                }
+                if y + 1 >= height {
+                    break;
+                }
+
+                ctx.text((0, y), entry.layer, heading_style);
+                layer = Some(entry.layer);
+                y += 1;
+            }
+
+            ctx.text((2, y), entry.keys, keys_style);
+            ctx.text((4 + keys_width, y), entry.help, style);
+            y += 1;
+        }
+    }
+}
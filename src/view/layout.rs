@@ -1,8 +1,15 @@
+use rustty::Size;
 use std::slice;
 use super::{DrawingContext, Widget};
 
 pub struct Layers<'a> {
-    widgets: Vec<Box<Widget + 'a>>,
+    widgets: Vec<Box<dyn Widget + 'a>>,
+}
+
+impl<'a> Default for Layers<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<'a> Layers<'a> {
@@ -10,22 +17,22 @@ impl<'a> Layers<'a> {
         Layers { widgets: Vec::new() }
     }
 
-    pub fn push_widget(&mut self, w: Box<Widget + 'a>) {
+    pub fn push_widget(&mut self, w: Box<dyn Widget + 'a>) {
         self.widgets.push(w)
     }
 }
 
 impl<'a> Widget for Layers<'a> {
     fn draw_on(&self, ctx: &mut DrawingContext) {
-        for w in self.widgets.iter() {
-            w.draw_on(ctx)
+        for (idx, w) in self.widgets.iter().enumerate() {
+            ctx.keyed(idx, |ctx| w.draw_on(ctx))
         }
     }
 }
 
 pub enum BoxItem<'a> {
-    Fixed(usize, Box<Widget + 'a>),
-    Expand(Box<Widget + 'a>),
+    Fixed(usize, Box<dyn Widget + 'a>),
+    Expand(Box<dyn Widget + 'a>),
 }
 
 pub struct BoxLayout<'a>(Vec<BoxItem<'a>>);
@@ -36,17 +43,14 @@ pub struct BoxLayoutIter<'a> {
 }
 
 impl<'a> Iterator for BoxLayoutIter<'a> {
-    type Item = (usize, &'a Box<Widget + 'a>);
+    type Item = (usize, &'a Box<dyn Widget + 'a>);
 
     fn next(&mut self) -> Option<Self::Item> {
-        let item = match self.layout_iter.next() {
-            None => return None,
-            Some(v) => v,
-        };
+        let item = self.layout_iter.next()?;
 
-        let (size, widget) = match item {
-            &BoxItem::Fixed(size, ref widget) => (size, widget),
-            &BoxItem::Expand(ref widget) => (self.expand_size, widget),
+        let (size, widget) = match *item {
+            BoxItem::Fixed(size, ref widget) => (size, widget),
+            BoxItem::Expand(ref widget) => (self.expand_size, widget),
         };
 
         Some((size, widget))
@@ -66,7 +70,7 @@ impl<'a> BoxLayout<'a> {
                 &BoxItem::Fixed(n, _) => n,
                 _ => 0,
             })
-            .fold(0, |n, m| n + m);
+            .sum::<usize>();
 
         BoxLayoutIter {
             layout_iter: self.0.iter(),
@@ -80,8 +84,75 @@ impl<'a> BoxLayout<'a> {
     }
 }
 
+/// Direction along which a layout arranges its children.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Orientation {
+    /// Children are placed side by side, left to right.
+    Horizontal,
+    /// Children are stacked on top of each other, top to bottom.
+    Vertical,
+}
+
+impl Orientation {
+    /// Returns the extent of `size` along the layout axis.
+    #[inline]
+    pub fn length(self, size: Size) -> usize {
+        match self {
+            Orientation::Horizontal => size.0,
+            Orientation::Vertical => size.1,
+        }
+    }
+}
+
+/// Draws `widget` into the slot starting at `offset` along the layout axis, `length` cells long.
+pub(crate) fn draw_slot(ctx: &mut DrawingContext,
+                        orientation: Orientation,
+                        offset: usize,
+                        length: usize,
+                        widget: &dyn Widget) {
+    let (width, height) = ctx.size();
+
+    ctx.save();
+    match orientation {
+        Orientation::Horizontal => {
+            ctx.translate((offset, 0));
+            ctx.clip((length, height));
+        }
+        Orientation::Vertical => {
+            ctx.translate((0, offset));
+            ctx.clip((width, length));
+        }
+    }
+    // FIXME: clip by shrinking
+    widget.draw_on(ctx);
+    ctx.restore();
+}
+
+fn draw_box(layout: &BoxLayout, orientation: Orientation, ctx: &mut DrawingContext) {
+    let total = orientation.length(ctx.size());
+
+    let mut pos = 0;
+
+    for (idx, (item_length, widget)) in layout.iter_sized_items(total).enumerate() {
+        ctx.keyed(idx, |ctx| draw_slot(ctx, orientation, pos, item_length, &**widget));
+
+        pos += item_length;
+
+        // we went off-screen, stop drawing
+        if pos >= total {
+            break;
+        }
+    }
+}
+
 pub struct VBox<'a>(BoxLayout<'a>);
 
+impl<'a> Default for VBox<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<'a> VBox<'a> {
     pub fn new() -> VBox<'a> {
         VBox(BoxLayout::new())
@@ -94,30 +165,18 @@ impl<'a> VBox<'a> {
 
 impl<'a> Widget for VBox<'a> {
     fn draw_on(&self, ctx: &mut DrawingContext) {
-        let (width, height) = ctx.size();
-
-        let mut y = 0;
-
-        for (item_height, widget) in self.0.iter_sized_items(height) {
-            ctx.save();
-            ctx.translate((0, y));
-            ctx.clip((width, item_height));
-            // FIXME: clip by shrinking
-            widget.draw_on(ctx);
-            ctx.restore();
-
-            y += item_height;
-
-            // we went off-screen, stop drawing
-            if y >= height {
-                break;
-            }
-        }
+        draw_box(&self.0, Orientation::Vertical, ctx)
     }
 }
 
 pub struct HBox<'a>(BoxLayout<'a>);
 
+impl<'a> Default for HBox<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<'a> HBox<'a> {
     pub fn new() -> HBox<'a> {
         HBox(BoxLayout::new())
@@ -130,24 +189,6 @@ impl<'a> HBox<'a> {
 
 impl<'a> Widget for HBox<'a> {
     fn draw_on(&self, ctx: &mut DrawingContext) {
-        let (width, height) = ctx.size();
-
-        let mut x = 0;
-
-        for (item_width, widget) in self.0.iter_sized_items(width) {
-            ctx.save();
-            ctx.translate((x, 0));
-            ctx.clip((item_width, height));
-            // FIXME: clip by shrinking
-            widget.draw_on(ctx);
-            ctx.restore();
-
-            x += item_width;
-
-            // we went off-screen, stop drawing
-            if x >= width {
-                break;
-            }
-        }
+        draw_box(&self.0, Orientation::Horizontal, ctx)
     }
 }
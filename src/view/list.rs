@@ -0,0 +1,330 @@
+use rustty::Event;
+use std::cell::Cell;
+use std::cmp::min;
+use super::{DrawingContext, Style, Widget};
+use super::controls::Control;
+use super::theme::roles;
+
+/// Items of a `ListView`. Like all text drawn by the crate, items must be ASCII.
+pub trait ListModel<'a> {
+    fn get_item(&'a self, idx: usize) -> &'a str;
+    fn num_items(&self) -> usize;
+}
+
+impl<'a, T: AsRef<str>> ListModel<'a> for Vec<T> {
+    fn get_item(&'a self, idx: usize) -> &'a str {
+        self[idx].as_ref()
+    }
+
+    fn num_items(&self) -> usize {
+        self.len()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectionMode {
+    /// Only the item under the cursor is selected.
+    Single,
+    /// Any number of items can be marked.
+    Multi,
+}
+
+/// Keys understood by a focused `ListView`. All other printable characters are appended to the
+/// incremental search query.
+pub mod keys {
+    pub const NEXT: char = '\x0e'; // Ctrl-N
+    pub const PREV: char = '\x10'; // Ctrl-P
+    pub const NEXT_MATCH: char = '\x13'; // Ctrl-S
+    pub const TOGGLE_MARK: char = '\t';
+    pub const CLEAR_SEARCH: char = '\x1b';
+    pub const BACKSPACE: &[char] = &['\x08', '\x7f'];
+}
+
+/// State of a `ListView`, kept by the application between frames.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ListState {
+    cursor: usize,
+    marked: Vec<usize>,
+    query: String,
+
+    // updated while drawing, once the number of visible rows is known
+    offset: Cell<usize>,
+}
+
+impl ListState {
+    pub fn new() -> ListState {
+        ListState::default()
+    }
+
+    #[inline]
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    #[inline]
+    pub fn set_cursor(&mut self, cursor: usize) {
+        self.cursor = cursor;
+    }
+
+    /// Indices of all marked items in ascending order. Only used in `SelectionMode::Multi`.
+    #[inline]
+    pub fn marked(&self) -> &[usize] {
+        &self.marked
+    }
+
+    #[inline]
+    pub fn is_marked(&self, idx: usize) -> bool {
+        self.marked.binary_search(&idx).is_ok()
+    }
+
+    pub fn toggle_mark(&mut self, idx: usize) {
+        match self.marked.binary_search(&idx) {
+            Ok(pos) => {
+                self.marked.remove(pos);
+            }
+            Err(pos) => self.marked.insert(pos, idx),
+        }
+    }
+
+    #[inline]
+    pub fn clear_marks(&mut self) {
+        self.marked.clear()
+    }
+
+    /// The current incremental search query.
+    #[inline]
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Index of the first visible item, as of the last redraw.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset.get()
+    }
+}
+
+/// Returns the byte range of the first case-insensitive occurrence of `query` in `text`.
+pub fn find_match(text: &str, query: &str) -> Option<(usize, usize)> {
+    if query.is_empty() {
+        return None;
+    }
+
+    text.as_bytes()
+        .windows(query.len())
+        .position(|window| window.eq_ignore_ascii_case(query.as_bytes()))
+        .map(|start| (start, start + query.len()))
+}
+
+/// Display status of a single item, passed to custom renderers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ItemStatus {
+    pub idx: usize,
+    pub cursor: bool,
+    pub marked: bool,
+    /// Byte range of the search match inside the item text.
+    pub matched: Option<(usize, usize)>,
+}
+
+pub type ItemRenderer<'a> = dyn Fn(&mut DrawingContext, &str, ItemStatus) + 'a;
+
+pub struct ListView<'a> {
+    model: &'a dyn ListModel<'a>,
+    state: &'a ListState,
+    mode: SelectionMode,
+    focused: bool,
+    renderer: Option<Box<ItemRenderer<'a>>>,
+
+    style: Option<Style>,
+    cursor_style: Option<Style>,
+    marked_style: Option<Style>,
+    match_style: Option<Style>,
+}
+
+impl<'a> ListView<'a> {
+    pub fn new(model: &'a dyn ListModel<'a>, state: &'a ListState) -> ListView<'a> {
+        ListView {
+            model,
+            state,
+            mode: SelectionMode::Single,
+            focused: false,
+            renderer: None,
+            style: None,
+            cursor_style: None,
+            marked_style: None,
+            match_style: None,
+        }
+    }
+
+    pub fn mode(mut self, mode: SelectionMode) -> ListView<'a> {
+        self.mode = mode;
+        self
+    }
+
+    pub fn focused(mut self, focused: bool) -> ListView<'a> {
+        self.focused = focused;
+        self
+    }
+
+    /// Replaces the default item rendering. The renderer draws a single row at `(0, 0)`; the
+    /// context is clipped to the row.
+    pub fn renderer(mut self, renderer: Box<ItemRenderer<'a>>) -> ListView<'a> {
+        self.renderer = Some(renderer);
+        self
+    }
+
+    pub fn style(mut self, style: Style) -> ListView<'a> {
+        self.style = Some(style);
+        self
+    }
+
+    pub fn cursor_style(mut self, style: Style) -> ListView<'a> {
+        self.cursor_style = Some(style);
+        self
+    }
+
+    pub fn marked_style(mut self, style: Style) -> ListView<'a> {
+        self.marked_style = Some(style);
+        self
+    }
+
+    /// Style used to highlight the part of an item matching the search query.
+    pub fn match_style(mut self, style: Style) -> ListView<'a> {
+        self.match_style = Some(style);
+        self
+    }
+
+    /// Returns the first item at or after `start` (wrapping around) matching `query`.
+    fn search_from(&self, start: usize, query: &str) -> Option<usize> {
+        let num = self.model.num_items();
+
+        (0..num)
+            .map(|n| (start + n) % num)
+            .find(|&idx| find_match(self.model.get_item(idx), query).is_some())
+    }
+
+    fn draw_item(&self, ctx: &mut DrawingContext, text: &str, status: ItemStatus) {
+        let style = if status.cursor {
+            ctx.themed(self.cursor_style, roles::LIST_CURSOR)
+        } else if status.marked {
+            ctx.themed(self.marked_style, roles::LIST_MARKED)
+        } else {
+            ctx.themed(self.style, roles::LIST)
+        };
+
+        let width = min(text.len(), ctx.size().0);
+        ctx.text((0, 0), &text[..width], style);
+
+        if let Some((start, end)) = status.matched {
+            if start < width {
+                let match_style = ctx.themed(self.match_style, roles::LIST_MATCH);
+                ctx.text((start, 0), &text[start..min(end, width)], match_style);
+            }
+        }
+    }
+}
+
+impl<'a> Control for ListView<'a> {
+    type State = ListState;
+
+    fn react(&self, ev: Event) -> Option<ListState> {
+        let num = self.model.num_items();
+
+        if !self.focused || num == 0 {
+            return None;
+        }
+
+        let Event::Key(ch) = ev;
+        let mut state = self.state.clone();
+
+        match ch {
+            keys::NEXT => state.cursor = min(state.cursor + 1, num - 1),
+            keys::PREV => state.cursor = state.cursor.saturating_sub(1),
+            keys::NEXT_MATCH => {
+                let start = (state.cursor + 1) % num;
+                state.cursor = self.search_from(start, &state.query)?;
+            }
+            keys::TOGGLE_MARK if self.mode == SelectionMode::Multi => {
+                state.toggle_mark(state.cursor)
+            }
+            keys::CLEAR_SEARCH => state.query.clear(),
+            ch if keys::BACKSPACE.contains(&ch) => {
+                state.query.pop()?;
+            }
+            ch if ch.is_ascii() && !ch.is_ascii_control() => {
+                state.query.push(ch);
+
+                // stay on the current item as long as it still matches
+                if let Some(idx) = self.search_from(state.cursor, &state.query) {
+                    state.cursor = idx;
+                }
+            }
+            _ => return None,
+        }
+
+        if state.cursor < state.offset.get() {
+            state.offset.set(state.cursor);
+        }
+
+        Some(state)
+    }
+}
+
+impl<'a> Widget for ListView<'a> {
+    fn draw_on(&self, ctx: &mut DrawingContext) {
+        let (cols, rows) = ctx.size();
+        let num = self.model.num_items();
+
+        if rows == 0 {
+            return;
+        }
+
+        // scroll just enough to keep the cursor visible
+        let cursor = min(self.state.cursor, num.saturating_sub(1));
+        let mut offset = min(self.state.offset.get(), cursor);
+        if cursor >= offset + rows {
+            offset = cursor + 1 - rows;
+        }
+        self.state.offset.set(offset);
+
+        for row in 0..rows {
+            let idx = offset + row;
+
+            if idx >= num {
+                break; // nothing more to draw, exit
+            }
+
+            let text = self.model.get_item(idx);
+            assert!(text.is_ascii());
+
+            let status = ItemStatus {
+                idx,
+                cursor: idx == cursor,
+                marked: self.mode == SelectionMode::Multi && self.state.is_marked(idx),
+                matched: find_match(text, &self.state.query),
+            };
+
+            ctx.save();
+            ctx.translate((0, row));
+            ctx.clip((cols, 1));
+            match self.renderer {
+                Some(ref renderer) => renderer(ctx, text, status),
+                None => self.draw_item(ctx, text, status),
+            }
+            ctx.restore();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find_match;
+
+    #[test]
+    fn find_match_ignores_case() {
+        assert_eq!(find_match("Hello World", "WOR"), Some((6, 9)));
+        assert_eq!(find_match("Hello World", "hello"), Some((0, 5)));
+        assert_eq!(find_match("ab", "abc"), None);
+        assert_eq!(find_match("ab", ""), None);
+    }
+}
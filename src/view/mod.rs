@@ -1,19 +1,41 @@
-use rustty::{Attr, Color};
+use rustty::Attr;
 
+pub mod ansi;
+mod buffer;
+pub mod canvas;
+pub mod chart;
+pub mod boxes;
+pub mod color;
 mod context;
+mod damage;
 pub mod controls;
 pub mod draw;
+pub mod heatmap;
+pub mod help;
 pub mod layout;
+pub mod list;
+pub mod palette;
+pub mod progress;
+pub mod split;
+pub mod state;
 pub mod table;
+pub mod terminal;
+pub mod text;
+pub mod theme;
 mod transform;
 pub mod window;
 
+pub use self::boxes::LineStyle;
+pub use self::buffer::{Buffer, Cell};
+pub use self::color::{Color, ColorSupport};
 pub use self::context::DrawingContext;
-pub use self::transform::{FixedSize, offset, sized, Translated};
+pub(crate) use self::damage::Damage;
+pub use self::transform::{FixedSize, Keyed, keyed, Memo, memo, offset, sized, Styled, styled,
+                          Translated};
 
 pub type Style = (Color, Color, Attr);
 const DEFAULT_STYLE: Style = (Color::Default, Color::Default, Attr::Default);
 
 pub trait Widget {
-    fn draw_on(&self, &mut DrawingContext);
+    fn draw_on(&self, ctx: &mut DrawingContext);
 }
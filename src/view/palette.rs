@@ -0,0 +1,333 @@
+use rustty::Event;
+use std::cmp::{min, Ordering};
+use super::{Cell, DrawingContext, Style, Widget};
+use super::controls::Control;
+use super::list::keys;
+use super::theme::roles;
+
+const SCORE_MATCH: i64 = 16;
+const SCORE_GAP_START: i64 = -3;
+const SCORE_GAP_EXTENSION: i64 = -1;
+const BONUS_BOUNDARY: i64 = 8;
+const BONUS_CAMEL: i64 = 7;
+const BONUS_CONSECUTIVE: i64 = 4;
+const BONUS_FIRST_CHAR_MULTIPLIER: i64 = 2;
+
+/// Result of matching a pattern against a text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    /// Byte positions of the matched characters in the text.
+    pub positions: Vec<usize>,
+}
+
+fn char_bonus(text: &[u8], idx: usize) -> i64 {
+    if idx == 0 {
+        return BONUS_BOUNDARY;
+    }
+
+    let (prev, cur) = (text[idx - 1], text[idx]);
+    if !prev.is_ascii_alphanumeric() && cur.is_ascii_alphanumeric() {
+        BONUS_BOUNDARY
+    } else if prev.is_ascii_lowercase() && cur.is_ascii_uppercase() ||
+              !prev.is_ascii_digit() && cur.is_ascii_digit() {
+        BONUS_CAMEL
+    } else {
+        0
+    }
+}
+
+/// Matches `pattern` as a subsequence of `text`, scoring it similar to fzf.
+///
+/// Matches at word boundaries and runs of consecutive characters score higher, gaps between
+/// matched characters lower the score. Matching is case-insensitive unless `pattern` contains
+/// an uppercase character.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    assert!(pattern.is_ascii() && text.is_ascii());
+
+    let pat = pattern.as_bytes();
+    let txt = text.as_bytes();
+
+    if pat.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+
+    let case_sensitive = pat.iter().any(|c| c.is_ascii_uppercase());
+    let eq = |a: u8, b: u8| if case_sensitive {
+        a == b
+    } else {
+        a.eq_ignore_ascii_case(&b)
+    };
+
+    // forward pass: find the earliest position the full pattern ends at
+    let mut pi = 0;
+    let mut end = 0;
+    for (ti, &c) in txt.iter().enumerate() {
+        if eq(c, pat[pi]) {
+            pi += 1;
+            if pi == pat.len() {
+                end = ti;
+                break;
+            }
+        }
+    }
+
+    if pi < pat.len() {
+        return None;
+    }
+
+    // backward pass: find the latest start for that end, yielding the shortest window
+    let mut pi = pat.len();
+    let mut start = end;
+    for ti in (0..end + 1).rev() {
+        if eq(txt[ti], pat[pi - 1]) {
+            pi -= 1;
+            if pi == 0 {
+                start = ti;
+                break;
+            }
+        }
+    }
+
+    // score the match within the window
+    let mut positions = Vec::with_capacity(pat.len());
+    let mut score = 0;
+    let mut pi = 0;
+    let mut last: Option<usize> = None;
+    let mut run_bonus = 0;
+
+    for ti in start..end + 1 {
+        if pi == pat.len() || !eq(txt[ti], pat[pi]) {
+            continue;
+        }
+
+        let mut bonus = char_bonus(txt, ti);
+
+        match last {
+            Some(l) if l + 1 == ti => {
+                // consecutive chars inherit the bonus of the start of their run
+                run_bonus = run_bonus.max(bonus).max(BONUS_CONSECUTIVE);
+                bonus = run_bonus;
+            }
+            Some(l) => {
+                score += SCORE_GAP_START + SCORE_GAP_EXTENSION * (ti - l - 2) as i64;
+                run_bonus = bonus;
+            }
+            None => run_bonus = bonus,
+        }
+
+        if pi == 0 {
+            bonus *= BONUS_FIRST_CHAR_MULTIPLIER;
+        }
+
+        score += SCORE_MATCH + bonus;
+        positions.push(ti);
+        last = Some(ti);
+        pi += 1;
+    }
+
+    Some(FuzzyMatch { score, positions })
+}
+
+/// Returns the indices of all `entries` matching `pattern`, best match first.
+pub fn rank<S: AsRef<str>>(entries: &[S], pattern: &str) -> Vec<(usize, FuzzyMatch)> {
+    let mut ranked: Vec<_> = entries.iter()
+        .enumerate()
+        .filter_map(|(idx, e)| fuzzy_match(pattern, e.as_ref()).map(|m| (idx, m)))
+        .collect();
+
+    // shorter entries win ties, then the original order
+    ranked.sort_by(|a, b| match b.1.score.cmp(&a.1.score) {
+        Ordering::Equal => {
+            entries[a.0]
+                .as_ref()
+                .len()
+                .cmp(&entries[b.0].as_ref().len())
+                .then(a.0.cmp(&b.0))
+        }
+        ord => ord,
+    });
+
+    ranked
+}
+
+/// State of a `CommandPalette`, kept by the application while the palette is open.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PaletteState {
+    query: String,
+    cursor: usize,
+}
+
+impl PaletteState {
+    pub fn new() -> PaletteState {
+        PaletteState::default()
+    }
+
+    #[inline]
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Position of the highlighted entry among the ranked matches.
+    #[inline]
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+}
+
+/// Outcome of a key press inside a `CommandPalette`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PaletteEvent {
+    /// Query or cursor changed, the palette stays open.
+    Update(PaletteState),
+    /// The entry with the given index was chosen.
+    Choose(usize),
+    /// The palette was dismissed.
+    Cancel,
+}
+
+/// Popup with a query line and the entries matching it, ranked by `fuzzy_match`.
+///
+/// Positioning is left to the caller, e.g. using `sized` and `offset` inside `Layers`.
+pub struct CommandPalette<'a> {
+    entries: &'a [&'a str],
+    state: &'a PaletteState,
+    prompt: &'a str,
+
+    bg_cell: Option<Cell>,
+    style: Option<Style>,
+    prompt_style: Option<Style>,
+    cursor_style: Option<Style>,
+    match_style: Option<Style>,
+}
+
+impl<'a> CommandPalette<'a> {
+    pub fn new(entries: &'a [&'a str], state: &'a PaletteState) -> CommandPalette<'a> {
+        CommandPalette {
+            entries,
+            state,
+            prompt: "> ",
+            bg_cell: None,
+            style: None,
+            prompt_style: None,
+            cursor_style: None,
+            match_style: None,
+        }
+    }
+
+    pub fn prompt(mut self, prompt: &'a str) -> CommandPalette<'a> {
+        assert!(prompt.is_ascii());
+        self.prompt = prompt;
+        self
+    }
+
+    pub fn bg_cell(mut self, bg_cell: Cell) -> CommandPalette<'a> {
+        self.bg_cell = Some(bg_cell);
+        self
+    }
+
+    pub fn style(mut self, style: Style) -> CommandPalette<'a> {
+        self.style = Some(style);
+        self
+    }
+
+    pub fn prompt_style(mut self, style: Style) -> CommandPalette<'a> {
+        self.prompt_style = Some(style);
+        self
+    }
+
+    pub fn cursor_style(mut self, style: Style) -> CommandPalette<'a> {
+        self.cursor_style = Some(style);
+        self
+    }
+
+    /// Style of matched characters.
+    pub fn match_style(mut self, style: Style) -> CommandPalette<'a> {
+        self.match_style = Some(style);
+        self
+    }
+}
+
+impl<'a> Control for CommandPalette<'a> {
+    type State = PaletteEvent;
+
+    fn react(&self, ev: Event) -> Option<PaletteEvent> {
+        let Event::Key(ch) = ev;
+        let mut state = self.state.clone();
+
+        match ch {
+            '\r' => {
+                return rank(self.entries, &state.query)
+                    .get(state.cursor)
+                    .map(|&(idx, _)| PaletteEvent::Choose(idx))
+            }
+            keys::CLEAR_SEARCH => return Some(PaletteEvent::Cancel),
+            keys::NEXT => {
+                let num = rank(self.entries, &state.query).len();
+                state.cursor = min(state.cursor + 1, num.saturating_sub(1));
+            }
+            keys::PREV => state.cursor = state.cursor.saturating_sub(1),
+            ch if keys::BACKSPACE.contains(&ch) => {
+                state.query.pop()?;
+                state.cursor = 0;
+            }
+            ch if ch.is_ascii() && !ch.is_ascii_control() => {
+                state.query.push(ch);
+                state.cursor = 0;
+            }
+            _ => return None,
+        }
+
+        if state == *self.state {
+            None
+        } else {
+            Some(PaletteEvent::Update(state))
+        }
+    }
+}
+
+impl<'a> Widget for CommandPalette<'a> {
+    fn draw_on(&self, ctx: &mut DrawingContext) {
+        let (cols, rows) = ctx.size();
+
+        if rows == 0 {
+            return;
+        }
+
+        let style = ctx.themed(self.style, roles::PALETTE);
+        let cursor_style = ctx.themed(self.cursor_style, roles::PALETTE_CURSOR);
+        let match_style = ctx.themed(self.match_style, roles::PALETTE_MATCH);
+
+        ctx.fill(self.bg_cell.unwrap_or_else(|| Cell::new(' ', style.0, style.1, style.2)));
+
+        // query line
+        let prompt_style = ctx.themed(self.prompt_style, roles::PALETTE_PROMPT);
+        ctx.text((0, 0), self.prompt, prompt_style);
+        ctx.text((self.prompt.len(), 0), &self.state.query, style);
+
+        let ranked = rank(self.entries, &self.state.query);
+        let visible = rows - 1;
+
+        // keep the cursor on screen
+        let offset = (self.state.cursor + 1).saturating_sub(visible);
+
+        for (row, &(idx, ref m)) in ranked.iter().skip(offset).take(visible).enumerate() {
+            let entry = self.entries[idx];
+            let style = if offset + row == self.state.cursor {
+                cursor_style
+            } else {
+                style
+            };
+
+            let width = min(entry.len(), cols);
+            ctx.text((0, row + 1), &entry[..width], style);
+
+            for &pos in m.positions.iter().filter(|&&pos| pos < width) {
+                ctx.text((pos, row + 1), &entry[pos..pos + 1], match_style);
+            }
+        }
+    }
+}
@@ -0,0 +1,330 @@
+use std::cmp::min;
+use std::time::Duration;
+use super::{Cell, DrawingContext, Style, Widget};
+use super::theme::roles;
+
+/// Left-aligned blocks, one to seven eighths of a cell wide.
+const HORIZONTAL_EIGHTHS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// Bottom-aligned blocks, one to seven eighths of a cell high.
+pub(crate) const VERTICAL_EIGHTHS: [char; 7] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇'];
+
+pub(crate) const FULL_BLOCK: char = '█';
+
+#[inline]
+fn clamp_fraction(fraction: f32) -> f32 {
+    if fraction.is_nan() {
+        0.0
+    } else {
+        fraction.clamp(0.0, 1.0)
+    }
+}
+
+/// Splits `fraction` of `len` cells into the number of full cells and the eighths of the
+/// following partial cell.
+#[inline]
+fn eighths(fraction: f32, len: usize) -> (usize, usize) {
+    let total = (clamp_fraction(fraction) * (len * 8) as f32).round() as usize;
+    (total / 8, total % 8)
+}
+
+/// Style used for text drawn on top of a filled cell, swapping fore- and background so it
+/// remains readable.
+#[inline]
+fn inverted(style: Style) -> Style {
+    (style.1, style.0, style.2)
+}
+
+/// Draws `text` centered on row `y`, switching to the inverted fill style where the text lies
+/// on top of the first `filled` columns.
+fn overlay_text(ctx: &mut DrawingContext,
+                y: usize,
+                text: &str,
+                filled: usize,
+                fill_style: Style,
+                text_style: Style) {
+    let width = ctx.size().0;
+    let len = min(text.len(), width);
+    let x0 = (width - len) / 2;
+
+    for (i, ch) in text[..len].chars().enumerate() {
+        let x = x0 + i;
+        let style = if x < filled {
+            inverted(fill_style)
+        } else {
+            text_style
+        };
+        ctx.set_cell((x, y), Cell::new(ch, style.0, style.1, style.2));
+    }
+}
+
+/// A horizontal bar showing the progress of a task with a resolution of an eighth of a cell.
+pub struct ProgressBar<'a> {
+    progress: f32,
+    label: &'a str,
+    percentage: bool,
+
+    filled_style: Option<Style>,
+    empty_style: Option<Style>,
+    label_style: Option<Style>,
+}
+
+impl<'a> Default for ProgressBar<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> ProgressBar<'a> {
+    pub fn new() -> ProgressBar<'a> {
+        ProgressBar {
+            progress: 0.0,
+            label: "",
+            percentage: false,
+            filled_style: None,
+            empty_style: None,
+            label_style: None,
+        }
+    }
+
+    /// Progress as a fraction between `0.0` and `1.0`.
+    pub fn progress(mut self, progress: f32) -> ProgressBar<'a> {
+        self.progress = clamp_fraction(progress);
+        self
+    }
+
+    /// Text centered on top of the bar.
+    pub fn label(mut self, label: &'a str) -> ProgressBar<'a> {
+        assert!(label.is_ascii());
+        self.label = label;
+        self
+    }
+
+    /// Append the percentage to the label.
+    pub fn percentage(mut self, percentage: bool) -> ProgressBar<'a> {
+        self.percentage = percentage;
+        self
+    }
+
+    /// Style of the filled part; the bar is drawn in the foreground color.
+    pub fn filled_style(mut self, style: Style) -> ProgressBar<'a> {
+        self.filled_style = Some(style);
+        self
+    }
+
+    pub fn empty_style(mut self, style: Style) -> ProgressBar<'a> {
+        self.empty_style = Some(style);
+        self
+    }
+
+    pub fn label_style(mut self, style: Style) -> ProgressBar<'a> {
+        self.label_style = Some(style);
+        self
+    }
+}
+
+impl<'a> Widget for ProgressBar<'a> {
+    fn draw_on(&self, ctx: &mut DrawingContext) {
+        let (cols, rows) = ctx.size();
+
+        if cols == 0 || rows == 0 {
+            return;
+        }
+
+        let (full, partial) = eighths(self.progress, cols);
+        let fs = ctx.themed(self.filled_style, roles::PROGRESS_FILLED);
+        let es = ctx.themed(self.empty_style, roles::PROGRESS_EMPTY);
+        let label_style = ctx.themed(self.label_style, roles::PROGRESS_LABEL);
+
+        for x in 0..cols {
+            let cell = if x < full {
+                Cell::new(FULL_BLOCK, fs.0, es.1, fs.2)
+            } else if x == full && partial > 0 {
+                Cell::new(HORIZONTAL_EIGHTHS[partial - 1], fs.0, es.1, fs.2)
+            } else {
+                Cell::new(' ', es.0, es.1, es.2)
+            };
+
+            for y in 0..rows {
+                ctx.set_cell((x, y), cell);
+            }
+        }
+
+        let text = match (self.label.is_empty(), self.percentage) {
+            (_, false) => self.label.to_owned(),
+            (true, true) => format!("{:.0}%", self.progress * 100.0),
+            (false, true) => format!("{} {:.0}%", self.label, self.progress * 100.0),
+        };
+
+        if !text.is_empty() {
+            overlay_text(ctx, rows / 2, &text, full, fs, label_style);
+        }
+    }
+}
+
+/// A vertical meter displaying `value` relative to `max`, filled from the bottom.
+pub struct Gauge<'a> {
+    value: f64,
+    max: f64,
+    label: &'a str,
+
+    filled_style: Option<Style>,
+    empty_style: Option<Style>,
+    label_style: Option<Style>,
+}
+
+impl<'a> Gauge<'a> {
+    pub fn new(value: f64, max: f64) -> Gauge<'a> {
+        Gauge {
+            value,
+            max,
+            label: "",
+            filled_style: None,
+            empty_style: None,
+            label_style: None,
+        }
+    }
+
+    /// Text drawn in the bottom row of the gauge.
+    pub fn label(mut self, label: &'a str) -> Gauge<'a> {
+        assert!(label.is_ascii());
+        self.label = label;
+        self
+    }
+
+    pub fn filled_style(mut self, style: Style) -> Gauge<'a> {
+        self.filled_style = Some(style);
+        self
+    }
+
+    pub fn empty_style(mut self, style: Style) -> Gauge<'a> {
+        self.empty_style = Some(style);
+        self
+    }
+
+    pub fn label_style(mut self, style: Style) -> Gauge<'a> {
+        self.label_style = Some(style);
+        self
+    }
+
+    pub fn ratio(&self) -> f32 {
+        if self.max > 0.0 {
+            (self.value / self.max) as f32
+        } else {
+            0.0
+        }
+    }
+}
+
+impl<'a> Widget for Gauge<'a> {
+    fn draw_on(&self, ctx: &mut DrawingContext) {
+        let (cols, rows) = ctx.size();
+
+        if cols == 0 || rows == 0 {
+            return;
+        }
+
+        let (full, partial) = eighths(self.ratio(), rows);
+        let fs = ctx.themed(self.filled_style, roles::PROGRESS_FILLED);
+        let es = ctx.themed(self.empty_style, roles::PROGRESS_EMPTY);
+        let label_style = ctx.themed(self.label_style, roles::PROGRESS_LABEL);
+
+        for level in 0..rows {
+            let cell = if level < full {
+                Cell::new(FULL_BLOCK, fs.0, es.1, fs.2)
+            } else if level == full && partial > 0 {
+                Cell::new(VERTICAL_EIGHTHS[partial - 1], fs.0, es.1, fs.2)
+            } else {
+                Cell::new(' ', es.0, es.1, es.2)
+            };
+
+            for x in 0..cols {
+                ctx.set_cell((x, rows - 1 - level), cell);
+            }
+        }
+
+        if !self.label.is_empty() {
+            let filled = if full > 0 { cols } else { 0 };
+            overlay_text(ctx, rows - 1, self.label, filled, fs, label_style);
+        }
+    }
+}
+
+/// Predefined frame sets for `Spinner`.
+pub mod spinners {
+    pub const LINE: &[char] = &['-', '\\', '|', '/'];
+    pub const DOTS: &[char] = &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+    pub const ARC: &[char] = &['◜', '◠', '◝', '◞', '◡', '◟'];
+    pub const QUADRANT: &[char] = &['▖', '▘', '▝', '▗'];
+}
+
+/// An animated activity indicator.
+///
+/// The frame shown is derived from `DrawingContext::elapsed`, so the animation advances with
+/// every redraw without the application keeping track of it.
+pub struct Spinner<'a> {
+    frames: &'a [char],
+    interval: Duration,
+    label: &'a str,
+    style: Option<Style>,
+}
+
+impl<'a> Default for Spinner<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Spinner<'a> {
+    pub fn new() -> Spinner<'a> {
+        Spinner {
+            frames: spinners::LINE,
+            interval: Duration::from_millis(100),
+            label: "",
+            style: None,
+        }
+    }
+
+    pub fn frames(mut self, frames: &'a [char]) -> Spinner<'a> {
+        assert!(!frames.is_empty());
+        self.frames = frames;
+        self
+    }
+
+    /// Time each frame is shown.
+    pub fn interval(mut self, interval: Duration) -> Spinner<'a> {
+        self.interval = interval;
+        self
+    }
+
+    pub fn label(mut self, label: &'a str) -> Spinner<'a> {
+        assert!(label.is_ascii());
+        self.label = label;
+        self
+    }
+
+    pub fn style(mut self, style: Style) -> Spinner<'a> {
+        self.style = Some(style);
+        self
+    }
+
+    /// Returns the frame to show after `elapsed` time.
+    pub fn frame_at(&self, elapsed: Duration) -> char {
+        let interval = self.interval.as_millis().max(1);
+        let idx = (elapsed.as_millis() / interval) % self.frames.len() as u128;
+        self.frames[idx as usize]
+    }
+}
+
+impl<'a> Widget for Spinner<'a> {
+    fn draw_on(&self, ctx: &mut DrawingContext) {
+        let ch = self.frame_at(ctx.elapsed());
+        let style = ctx.themed(self.style, roles::SPINNER);
+
+        ctx.set_cell((0, 0), Cell::new(ch, style.0, style.1, style.2));
+
+        if !self.label.is_empty() {
+            ctx.text((2, 0), self.label, style);
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use rustty::Attr;
+use std::cmp::{max, min};
+use super::{Cell, Color, DrawingContext, Widget};
+use super::layout::{draw_slot, Orientation};
+
+/// One of the two panes of a `Split`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pane {
+    First,
+    Second,
+}
+
+/// Persistent state of a `Split`.
+///
+/// The ratio is the share of the available space (excluding the divider) given to the first
+/// pane. It is kept by the application between frames and passed to the `Split` on each redraw.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SplitState {
+    ratio: f32,
+    collapsed: Option<Pane>,
+    dragging: bool,
+}
+
+impl Default for SplitState {
+    fn default() -> Self {
+        Self::new(0.5)
+    }
+}
+
+impl SplitState {
+    pub fn new(ratio: f32) -> SplitState {
+        SplitState {
+            ratio: clamp_ratio(ratio),
+            collapsed: None,
+            dragging: false,
+        }
+    }
+
+    #[inline]
+    pub fn ratio(&self) -> f32 {
+        self.ratio
+    }
+
+    #[inline]
+    pub fn set_ratio(&mut self, ratio: f32) {
+        self.ratio = clamp_ratio(ratio);
+    }
+
+    /// Moves the divider by `delta`, e.g. in response to a key press. Negative values grow the
+    /// second pane.
+    #[inline]
+    pub fn adjust(&mut self, delta: f32) {
+        let ratio = self.ratio + delta;
+        self.set_ratio(ratio);
+    }
+
+    #[inline]
+    pub fn collapsed(&self) -> Option<Pane> {
+        self.collapsed
+    }
+
+    /// Hides `pane`, giving all space to the other one. The ratio is kept, so restoring the
+    /// pane returns the divider to its previous position.
+    #[inline]
+    pub fn collapse(&mut self, pane: Pane) {
+        self.collapsed = Some(pane);
+        self.dragging = false;
+    }
+
+    #[inline]
+    pub fn restore(&mut self) {
+        self.collapsed = None;
+    }
+
+    pub fn toggle_collapse(&mut self, pane: Pane) {
+        if self.collapsed == Some(pane) {
+            self.restore()
+        } else {
+            self.collapse(pane)
+        }
+    }
+
+    #[inline]
+    pub fn is_dragging(&self) -> bool {
+        self.dragging
+    }
+
+    /// Starts dragging the divider. Has no effect while a pane is collapsed.
+    #[inline]
+    pub fn begin_drag(&mut self) {
+        self.dragging = self.collapsed.is_none();
+    }
+
+    /// Moves the divider to `pos` cells from the start of a split that is `total` cells long
+    /// along its orientation. Ignored unless a drag is in progress.
+    pub fn drag_to(&mut self, pos: usize, total: usize) {
+        if !self.dragging || total < 2 {
+            return;
+        }
+
+        // the divider itself takes up one cell, which is not part of the ratio
+        self.set_ratio(pos as f32 / (total - 1) as f32);
+    }
+
+    #[inline]
+    pub fn end_drag(&mut self) {
+        self.dragging = false;
+    }
+
+    /// Returns the position of the divider in a split `total` cells long, or `None` if a pane
+    /// is collapsed and no divider is shown.
+    pub fn divider_pos(&self, total: usize, min_sizes: (usize, usize)) -> Option<usize> {
+        if self.collapsed.is_some() || total == 0 {
+            return None;
+        }
+
+        Some(split_sizes(total - 1, self.ratio, min_sizes).0)
+    }
+}
+
+#[inline]
+fn clamp_ratio(ratio: f32) -> f32 {
+    if ratio.is_nan() {
+        0.5
+    } else {
+        ratio.clamp(0.0, 1.0)
+    }
+}
+
+/// Divides `available` cells between two panes according to `ratio`, honoring minimum sizes.
+/// If both minimums cannot be satisfied, the first pane takes precedence.
+fn split_sizes(available: usize, ratio: f32, min_sizes: (usize, usize)) -> (usize, usize) {
+    let wanted = (available as f32 * ratio).round() as usize;
+    let upper = available.saturating_sub(min_sizes.1);
+
+    let first = min(max(min(wanted, upper), min_sizes.0), available);
+
+    (first, available - first)
+}
+
+/// Two widgets separated by a movable divider.
+///
+/// With `Orientation::Horizontal`, the panes are placed side by side and the divider is a
+/// vertical line; with `Orientation::Vertical`, they are stacked.
+pub struct Split<'a> {
+    orientation: Orientation,
+    first: Box<dyn Widget + 'a>,
+    second: Box<dyn Widget + 'a>,
+    state: SplitState,
+    min_sizes: (usize, usize),
+    divider_cell: Cell,
+    drag_cell: Cell,
+}
+
+impl<'a> Split<'a> {
+    pub fn new(orientation: Orientation,
+               first: Box<dyn Widget + 'a>,
+               second: Box<dyn Widget + 'a>)
+               -> Split<'a> {
+        let ch = match orientation {
+            Orientation::Horizontal => '│',
+            Orientation::Vertical => '─',
+        };
+
+        Split {
+            orientation,
+            first,
+            second,
+            state: SplitState::default(),
+            min_sizes: (0, 0),
+            divider_cell: Cell::new(ch, Color::Default, Color::Default, Attr::Default),
+            drag_cell: Cell::new(ch, Color::Default, Color::Default, Attr::Bold),
+        }
+    }
+
+    #[inline]
+    pub fn state(mut self, state: SplitState) -> Split<'a> {
+        self.state = state;
+        self
+    }
+
+    #[inline]
+    pub fn min_sizes(mut self, first: usize, second: usize) -> Split<'a> {
+        self.min_sizes = (first, second);
+        self
+    }
+
+    #[inline]
+    pub fn divider_cell(mut self, cell: Cell) -> Split<'a> {
+        self.divider_cell = cell;
+        self
+    }
+
+    /// Cell used for the divider while it is being dragged.
+    #[inline]
+    pub fn drag_cell(mut self, cell: Cell) -> Split<'a> {
+        self.drag_cell = cell;
+        self
+    }
+}
+
+impl<'a> Widget for Split<'a> {
+    fn draw_on(&self, ctx: &mut DrawingContext) {
+        let total = self.orientation.length(ctx.size());
+
+        match self.state.collapsed() {
+            Some(Pane::First) => {
+                ctx.keyed(1, |ctx| draw_slot(ctx, self.orientation, 0, total, &*self.second));
+                return;
+            }
+            Some(Pane::Second) => {
+                ctx.keyed(0, |ctx| draw_slot(ctx, self.orientation, 0, total, &*self.first));
+                return;
+            }
+            None => (),
+        }
+
+        let divider = match self.state.divider_pos(total, self.min_sizes) {
+            Some(pos) => pos,
+            None => return,
+        };
+
+        // the panes keep their identity when the other one is collapsed
+        ctx.keyed(0, |ctx| draw_slot(ctx, self.orientation, 0, divider, &*self.first));
+        ctx.keyed(1, |ctx| {
+            draw_slot(ctx,
+                      self.orientation,
+                      divider + 1,
+                      total - divider - 1,
+                      &*self.second)
+        });
+
+        let cell = if self.state.is_dragging() {
+            self.drag_cell
+        } else {
+            self.divider_cell
+        };
+
+        let (width, height) = ctx.size();
+        match self.orientation {
+            Orientation::Horizontal if height > 0 => {
+                ctx.line((divider, 0), (divider, height - 1), cell)
+            }
+            Orientation::Vertical if width > 0 => {
+                ctx.line((0, divider), (width - 1, divider), cell)
+            }
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,256 @@
+//! State of widgets retained between frames.
+//!
+//! Widgets are rebuilt by `Application::view` for every frame, so they cannot hold on to
+//! anything themselves. Instead, state like scroll positions is kept in a `StateStore` under
+//! the identity of the widget, and looked up again when the widget is drawn next time.
+//!
+//! The identity of a widget is its path in the widget tree: containers give each child the
+//! index it is drawn at, see `DrawingContext::keyed`. Where this is not stable, e.g. for items
+//! of a list that are inserted and removed, an explicit key can be given with `keyed` instead.
+//! State not used during a frame is dropped at its end, like the widget it belonged to.
+
+use std::any::{Any, TypeId};
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+
+/// A segment of a `WidgetId`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Key {
+    /// Position among the children of a container.
+    Index(usize),
+    /// Explicit key, identifying a widget wherever it is in the tree.
+    Name(Cow<'static, str>),
+}
+
+impl From<usize> for Key {
+    fn from(idx: usize) -> Key {
+        Key::Index(idx)
+    }
+}
+
+impl From<&'static str> for Key {
+    fn from(name: &'static str) -> Key {
+        Key::Name(Cow::Borrowed(name))
+    }
+}
+
+impl From<String> for Key {
+    fn from(name: String) -> Key {
+        Key::Name(Cow::Owned(name))
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Key::Index(idx) => write!(f, "{}", idx),
+            Key::Name(ref name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Identity of a widget, made of the keys from the root or the closest explicit key.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct WidgetId(Vec<Key>);
+
+impl WidgetId {
+    /// The widget at the root of the tree.
+    #[inline]
+    pub fn root() -> WidgetId {
+        WidgetId(Vec::new())
+    }
+
+    /// The child of this widget at `key`. An explicit key starts a new path.
+    pub fn child<K: Into<Key>>(&self, key: K) -> WidgetId {
+        match key.into() {
+            key @ Key::Name(_) => WidgetId(vec![key]),
+            key => {
+                let mut keys = self.0.clone();
+                keys.push(key);
+                WidgetId(keys)
+            }
+        }
+    }
+
+    #[inline]
+    pub fn keys(&self) -> &[Key] {
+        &self.0
+    }
+
+    /// Returns whether `self` is `other` or one of its descendants within the same path.
+    pub fn starts_with(&self, other: &WidgetId) -> bool {
+        self.0.starts_with(&other.0)
+    }
+}
+
+impl fmt::Display for WidgetId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "/")?;
+        for (idx, key) in self.0.iter().enumerate() {
+            if idx > 0 {
+                write!(f, "/")?;
+            }
+            write!(f, "{}", key)?;
+        }
+        Ok(())
+    }
+}
+
+struct Entry {
+    value: Box<dyn Any>,
+    frame: u64,
+}
+
+/// Widget state kept between frames, see the module documentation.
+///
+/// The store is owned by the application and handed to the main loop through
+/// `Application::states`. It is used through a shared reference while drawing. A widget may
+/// keep states of several types, each of them separately.
+#[derive(Default)]
+pub struct StateStore {
+    entries: RefCell<HashMap<(WidgetId, TypeId), Entry>>,
+    frame: Cell<u64>,
+}
+
+impl StateStore {
+    pub fn new() -> StateStore {
+        StateStore::default()
+    }
+
+    /// Runs `f` on the state of type `T` of `id`, starting from the default if there is none
+    /// yet.
+    ///
+    /// The store is borrowed while `f` runs, so `f` must not access the store itself.
+    pub fn with<T, R, F>(&self, id: &WidgetId, f: F) -> R
+        where T: Any + Default,
+              F: FnOnce(&mut T) -> R
+    {
+        let mut entries = self.entries.borrow_mut();
+        let frame = self.frame.get();
+        let entry = entries.entry((id.clone(), TypeId::of::<T>())).or_insert_with(|| {
+            Entry {
+                value: Box::new(T::default()),
+                frame,
+            }
+        });
+
+        entry.frame = frame;
+        f(entry.value.downcast_mut().unwrap())
+    }
+
+    /// A copy of the state of `id`, if it has one of type `T`.
+    pub fn get<T: Any + Clone>(&self, id: &WidgetId) -> Option<T> {
+        let key = (id.clone(), TypeId::of::<T>());
+        self.entries.borrow().get(&key).and_then(|e| e.value.downcast_ref::<T>()).cloned()
+    }
+
+    /// Replaces the state of `id`, e.g. to scroll a widget in response to an action.
+    pub fn set<T: Any>(&self, id: &WidgetId, value: T) {
+        let frame = self.frame.get();
+        self.entries.borrow_mut().insert((id.clone(), TypeId::of::<T>()),
+                                         Entry {
+                                             value: Box::new(value),
+                                             frame,
+                                         });
+    }
+
+    /// Drops all states of `id`.
+    pub fn remove(&self, id: &WidgetId) {
+        self.entries.borrow_mut().retain(|key, _| key.0 != *id);
+    }
+
+    pub fn clear(&self) {
+        self.entries.borrow_mut().clear();
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.borrow().len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Keeps the states of `id` and its descendants, for widgets skipped during the frame.
+    pub(crate) fn touch(&self, id: &WidgetId) {
+        let frame = self.frame.get();
+        for (key, entry) in self.entries.borrow_mut().iter_mut() {
+            if key.0.starts_with(id) {
+                entry.frame = frame;
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) fn begin_frame(&self) {
+        self.frame.set(self.frame.get() + 1);
+    }
+
+    /// Drops the states not used since the frame began.
+    pub(crate) fn end_frame(&self) {
+        let frame = self.frame.get();
+        self.entries.borrow_mut().retain(|_, entry| entry.frame == frame);
+    }
+}
+
+impl fmt::Debug for StateStore {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.entries.borrow().keys().map(|key| &key.0)).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StateStore, WidgetId};
+
+    #[derive(Clone, Debug, Default, PartialEq)]
+    struct Scroll(usize);
+
+    #[derive(Clone, Debug, Default, PartialEq)]
+    struct Selection(Option<usize>);
+
+    #[test]
+    fn state_survives_frames_while_used() {
+        let store = StateStore::new();
+        let id = WidgetId::root().child(0);
+
+        store.begin_frame();
+        store.with(&id, |s: &mut Scroll| s.0 = 3);
+        store.end_frame();
+
+        store.begin_frame();
+        assert_eq!(store.with(&id, |s: &mut Scroll| s.0), 3);
+        store.end_frame();
+        assert_eq!(store.get(&id), Some(Scroll(3)));
+
+        // not used during a frame, so dropped at its end
+        store.begin_frame();
+        store.end_frame();
+        assert_eq!(store.get::<Scroll>(&id), None);
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn states_of_different_types_at_one_id() {
+        let store = StateStore::new();
+        let id = WidgetId::root().child(1);
+
+        for frame in 0..3 {
+            store.begin_frame();
+            store.with(&id, |s: &mut Scroll| s.0 += 1);
+            store.with(&id, |s: &mut Selection| s.0 = Some(frame));
+            store.end_frame();
+        }
+
+        assert_eq!(store.get(&id), Some(Scroll(3)));
+        assert_eq!(store.get(&id), Some(Selection(Some(2))));
+        assert_eq!(store.len(), 2);
+
+        store.remove(&id);
+        assert!(store.is_empty());
+    }
+}
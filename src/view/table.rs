@@ -1,14 +1,21 @@
-use std::ascii::AsciiExt;
 use std::cmp::{max, min};
-use super::{DEFAULT_STYLE, DrawingContext, Style, Widget};
+use super::{Cell, DrawingContext, Style, Widget};
+use super::text::Line;
+use super::theme::roles;
 
-pub type RowIter<'a> = Box<Iterator<Item = &'a str>>;
+pub type RowIter<'a> = Box<dyn Iterator<Item = &'a str>>;
 
 pub trait TableModel<'a> {
-    fn headers(&'a self) -> Box<Iterator<Item = &'a str> + 'a>;
-    fn get_row(&'a self, row_id: usize) -> Box<Iterator<Item = &'a str> + 'a>;
+    fn headers(&'a self) -> Box<dyn Iterator<Item = &'a str> + 'a>;
+    fn get_row(&'a self, row_id: usize) -> Box<dyn Iterator<Item = &'a str> + 'a>;
     fn num_rows(&self) -> usize;
     fn num_cols(&self) -> usize;
+
+    /// Cells of a row with styled spans, drawn on top of the cell style. Defaults to the plain
+    /// cells of `get_row`.
+    fn get_styled_row(&'a self, row_id: usize) -> Box<dyn Iterator<Item = Line<'a>> + 'a> {
+        Box::new(self.get_row(row_id).map(Line::from))
+    }
 }
 
 pub struct SimpleModel {
@@ -19,7 +26,7 @@ pub struct SimpleModel {
 impl SimpleModel {
     pub fn new(headers: Vec<String>) -> SimpleModel {
         SimpleModel {
-            headers: headers,
+            headers,
             rows: Vec::new(),
         }
     }
@@ -30,11 +37,11 @@ impl SimpleModel {
 }
 
 impl<'a> TableModel<'a> for SimpleModel {
-    fn headers(&'a self) -> Box<Iterator<Item = &'a str> + 'a> {
+    fn headers(&'a self) -> Box<dyn Iterator<Item = &'a str> + 'a> {
         Box::new(self.headers.iter().map(|x| x.as_str()))
     }
 
-    fn get_row(&'a self, row_id: usize) -> Box<Iterator<Item = &'a str> + 'a> {
+    fn get_row(&'a self, row_id: usize) -> Box<dyn Iterator<Item = &'a str> + 'a> {
         Box::new(self.rows[row_id].iter().map(|x| x.as_str()))
     }
 
@@ -48,37 +55,60 @@ impl<'a> TableModel<'a> for SimpleModel {
 }
 
 pub struct TableView<'a> {
-    model: &'a TableModel<'a>,
+    model: &'a dyn TableModel<'a>,
     col_width: Vec<i32>,
-    header_style: Style,
-    cell_style: Style,
+    header_style: Option<Style>,
+    cell_style: Option<Style>,
+    selected_style: Option<Style>,
+    offset: Option<usize>,
+    selected: Option<usize>,
+}
+
+/// Scroll position a `TableView` retains between frames.
+#[derive(Default)]
+struct TableScroll {
     offset: usize,
 }
 
 impl<'a> TableView<'a> {
-    pub fn new(model: &'a TableModel<'a>, col_width: Vec<i32>) -> TableView<'a> {
+    pub fn new(model: &'a dyn TableModel<'a>, col_width: Vec<i32>) -> TableView<'a> {
         assert_eq!(model.num_cols(), col_width.len());
         TableView {
-            model: model,
-            col_width: col_width,
-            header_style: DEFAULT_STYLE,
-            cell_style: DEFAULT_STYLE,
-            offset: 0,
+            model,
+            col_width,
+            header_style: None,
+            cell_style: None,
+            selected_style: None,
+            offset: None,
+            selected: None,
         }
     }
 
     pub fn header_style(mut self, style: Style) -> TableView<'a> {
-        self.header_style = style;
+        self.header_style = Some(style);
         self
     }
 
     pub fn cell_style(mut self, style: Style) -> TableView<'a> {
-        self.cell_style = style;
+        self.cell_style = Some(style);
+        self
+    }
+
+    pub fn selected_style(mut self, style: Style) -> TableView<'a> {
+        self.selected_style = Some(style);
         self
     }
 
+    /// First row shown. Without it, the table keeps its scroll position in the state store
+    /// and only scrolls to keep the selected row visible.
     pub fn offset(mut self, offset: usize) -> TableView<'a> {
-        self.offset = offset;
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Highlights the row at `selected`.
+    pub fn selected(mut self, selected: Option<usize>) -> TableView<'a> {
+        self.selected = selected;
         self
     }
 }
@@ -97,7 +127,7 @@ impl<'a> Widget for TableView<'a> {
         let expand_size = cols.saturating_sub(self.col_width
             .iter()
             .map(|&z| max(z, 0))
-            .fold(0, |n, m| n + m) as usize);
+            .sum::<i32>() as usize);
 
         // cache widths
         let widths: Vec<_> = self.col_width
@@ -105,6 +135,10 @@ impl<'a> Widget for TableView<'a> {
             .map(|&z| if z < 0 { expand_size } else { z as usize })
             .collect();
 
+        let header_style = ctx.themed(self.header_style, roles::TABLE_HEADER);
+        let cell_style = ctx.themed(self.cell_style, roles::TABLE_CELL);
+        let selected_style = ctx.themed(self.selected_style, roles::TABLE_SELECTED);
+
         // first, draw header
         let mut x_pos = 0;
         for (header, &width) in self.model.headers().zip(widths.iter()) {
@@ -112,7 +146,7 @@ impl<'a> Widget for TableView<'a> {
             assert!(header.is_ascii());
             ctx.text((x_pos, 0),
                      &header[0..min(header.len(), width)],
-                     self.header_style);
+                     header_style);
             x_pos += width;
         }
 
@@ -122,20 +156,45 @@ impl<'a> Widget for TableView<'a> {
         }
 
         let num_rows = self.model.num_rows();
-        for i in 0..(rows - 1) {
+        let visible = rows - 1;
+        let offset = match self.offset {
+            Some(offset) => offset,
+            None => {
+                ctx.with_state(|scroll: &mut TableScroll| {
+                    // scroll just enough to keep the selected row visible
+                    if let Some(selected) = self.selected {
+                        if selected < scroll.offset {
+                            scroll.offset = selected;
+                        } else if visible > 0 && selected >= scroll.offset + visible {
+                            scroll.offset = selected + 1 - visible;
+                        }
+                    }
+                    scroll.offset = min(scroll.offset, num_rows.saturating_sub(visible));
+                    scroll.offset
+                })
+            }
+        };
+
+        for i in 0..visible {
             let row_pos = i + 1;
-            let row_idx = i + self.offset;
+            let row_idx = i + offset;
 
             if row_idx >= num_rows {
                 break; // nothing more to draw, exit
             }
 
+            let style = if self.selected == Some(row_idx) {
+                let (fg, bg, attr) = selected_style;
+                ctx.fill_rect((0, row_pos), (cols, 1), Cell::new(' ', fg, bg, attr));
+                selected_style
+            } else {
+                cell_style
+            };
+
             x_pos = 0;
-            for (cell, &width) in self.model.get_row(row_idx).zip(widths.iter()) {
-                assert!(cell.is_ascii());
-                ctx.text((x_pos, row_pos),
-                         &cell[0..min(cell.len(), width)],
-                         self.cell_style);
+            for (mut cell, &width) in self.model.get_styled_row(row_idx).zip(widths.iter()) {
+                cell.truncate(width);
+                ctx.styled_text((x_pos, row_pos), &cell, style);
                 x_pos += width;
             }
         }
@@ -0,0 +1,118 @@
+use pty::Pty;
+use rustty::{Attr, Event};
+use std::cmp::min;
+use super::{Cell, DrawingContext, Style, Widget};
+use super::controls::Control;
+use super::theme::{attr_from_bits, roles, StyleSpec};
+
+/// Scroll position of a `TerminalView`, kept by the application between frames.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TerminalState {
+    scroll: usize,
+}
+
+impl TerminalState {
+    pub fn new() -> TerminalState {
+        TerminalState::default()
+    }
+
+    /// Number of lines scrolled back, 0 when showing the screen.
+    #[inline]
+    pub fn scroll(&self) -> usize {
+        self.scroll
+    }
+
+    /// Scrolls back by `n` lines, at most to the top of the scrollback.
+    pub fn scroll_up(&mut self, pty: &Pty, n: usize) {
+        self.scroll = min(self.scroll + n, pty.screen().scrollback_len());
+    }
+
+    #[inline]
+    pub fn scroll_down(&mut self, n: usize) {
+        self.scroll = self.scroll.saturating_sub(n);
+    }
+}
+
+/// Shows the screen of a program running in a `Pty`.
+///
+/// The terminal is resized to the area the view is drawn in. When focused, keys are sent to
+/// the program and the view returns to the bottom of the scrollback.
+pub struct TerminalView<'a> {
+    pty: &'a Pty,
+    state: &'a TerminalState,
+    focused: bool,
+    style: Option<Style>,
+}
+
+impl<'a> TerminalView<'a> {
+    pub fn new(pty: &'a Pty, state: &'a TerminalState) -> TerminalView<'a> {
+        TerminalView {
+            pty,
+            state,
+            focused: false,
+            style: None,
+        }
+    }
+
+    #[inline]
+    pub fn focused(mut self, focused: bool) -> TerminalView<'a> {
+        self.focused = focused;
+        self
+    }
+
+    /// The style programs draw on top of, used for their default colors.
+    #[inline]
+    pub fn style(mut self, style: Style) -> TerminalView<'a> {
+        self.style = Some(style);
+        self
+    }
+}
+
+impl<'a> Widget for TerminalView<'a> {
+    fn draw_on(&self, ctx: &mut DrawingContext) {
+        let size = ctx.size();
+        if size.0 == 0 || size.1 == 0 {
+            return;
+        }
+
+        // a failed resize leaves the program at its old size, which is drawn clipped
+        let _ = self.pty.resize(size);
+
+        let style = ctx.themed(self.style, roles::TERMINAL);
+        let base = StyleSpec::from(style);
+        let screen = self.pty.screen();
+        let scroll = min(self.state.scroll, screen.scrollback_len());
+
+        for y in 0..size.1 {
+            let row = screen.row(scroll, y).unwrap_or(&[]);
+            for x in 0..size.0 {
+                let (ch, spec) = row.get(x).cloned().unwrap_or((' ', StyleSpec::new()));
+                let (fg, bg, attr) = base.patch(spec).apply(style);
+                ctx.set_cell((x, y), Cell::new(ch, fg, bg, attr));
+            }
+        }
+
+        // the cursor of the program is shown by reversing the cell under it
+        let (cx, cy) = screen.cursor();
+        if self.focused && scroll == 0 && screen.cursor_visible() {
+            if let Some(cell) = ctx.get_mut_cell((cx, cy)) {
+                let attr = cell.attrs() as u8 ^ Attr::Reverse as u8;
+                cell.set_attrs(attr_from_bits(attr));
+            }
+        }
+    }
+}
+
+impl<'a> Control for TerminalView<'a> {
+    type State = TerminalState;
+
+    fn react(&self, ev: Event) -> Option<TerminalState> {
+        if !self.focused || !self.pty.is_running() {
+            return None;
+        }
+
+        let Event::Key(ch) = ev;
+        self.pty.send_key(ch).ok()?;
+        Some(TerminalState { scroll: 0 })
+    }
+}
@@ -0,0 +1,344 @@
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+use super::{DrawingContext, Style, Widget};
+use super::theme::{parse_style, roles, StyleSpec};
+
+/// A piece of text in a single style.
+///
+/// The style is put on top of the one the span is drawn with, so a span that is only bold
+/// keeps the surrounding colors.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Span<'a> {
+    pub content: Cow<'a, str>,
+    pub style: StyleSpec,
+}
+
+impl<'a> Span<'a> {
+    /// A span without a style of its own.
+    pub fn raw<S: Into<Cow<'a, str>>>(content: S) -> Span<'a> {
+        Span {
+            content: content.into(),
+            style: StyleSpec::new(),
+        }
+    }
+
+    pub fn styled<S, T>(content: S, style: T) -> Span<'a>
+        where S: Into<Cow<'a, str>>,
+              T: Into<StyleSpec>
+    {
+        Span {
+            content: content.into(),
+            style: style.into(),
+        }
+    }
+
+    /// Puts `style` on top of the style of the span.
+    #[inline]
+    pub fn patch<T: Into<StyleSpec>>(mut self, style: T) -> Span<'a> {
+        self.style = self.style.patch(style.into());
+        self
+    }
+
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.content.len()
+    }
+}
+
+impl<'a> From<&'a str> for Span<'a> {
+    fn from(s: &'a str) -> Span<'a> {
+        Span::raw(s)
+    }
+}
+
+impl From<String> for Span<'static> {
+    fn from(s: String) -> Span<'static> {
+        Span::raw(s)
+    }
+}
+
+/// A single line of text, made of differently styled spans.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Line<'a> {
+    pub spans: Vec<Span<'a>>,
+}
+
+impl<'a> Line<'a> {
+    pub fn new(spans: Vec<Span<'a>>) -> Line<'a> {
+        Line { spans }
+    }
+
+    /// Appends `span`.
+    #[inline]
+    pub fn span<S: Into<Span<'a>>>(mut self, span: S) -> Line<'a> {
+        self.spans.push(span.into());
+        self
+    }
+
+    /// Puts `style` on top of the styles of all spans.
+    pub fn patch<T: Into<StyleSpec>>(mut self, style: T) -> Line<'a> {
+        let style = style.into();
+        for span in &mut self.spans {
+            span.style = span.style.patch(style);
+        }
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.spans.iter().map(Span::width).sum()
+    }
+
+    /// Cuts off everything beyond `width` columns.
+    pub fn truncate(&mut self, width: usize) {
+        let mut x = 0;
+        let mut end = self.spans.len();
+
+        for (idx, span) in self.spans.iter_mut().enumerate() {
+            if x + span.width() > width {
+                let cut = width - x;
+                match span.content {
+                    Cow::Borrowed(s) => span.content = Cow::Borrowed(&s[..cut]),
+                    Cow::Owned(ref mut s) => s.truncate(cut),
+                }
+                end = idx + 1;
+                break;
+            }
+            x += span.width();
+        }
+
+        self.spans.truncate(end);
+    }
+}
+
+impl<'a> From<&'a str> for Line<'a> {
+    fn from(s: &'a str) -> Line<'a> {
+        Line::new(vec![Span::raw(s)])
+    }
+}
+
+impl From<String> for Line<'static> {
+    fn from(s: String) -> Line<'static> {
+        Line::new(vec![Span::raw(s)])
+    }
+}
+
+impl<'a> From<Span<'a>> for Line<'a> {
+    fn from(span: Span<'a>) -> Line<'a> {
+        Line::new(vec![span])
+    }
+}
+
+impl<'a> From<Vec<Span<'a>>> for Line<'a> {
+    fn from(spans: Vec<Span<'a>>) -> Line<'a> {
+        Line::new(spans)
+    }
+}
+
+/// Placement of text along one axis of the area it is drawn in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Start,
+    Center,
+    End,
+}
+
+impl Align {
+    /// Offset of something `len` long inside `total`, 0 if it does not fit.
+    #[inline]
+    pub fn offset(self, len: usize, total: usize) -> usize {
+        let free = total.saturating_sub(len);
+        match self {
+            Align::Start => 0,
+            Align::Center => free / 2,
+            Align::End => free,
+        }
+    }
+}
+
+/// Styled text of several lines, drawn from the top left corner of the context by default.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Text<'a> {
+    pub lines: Vec<Line<'a>>,
+    style: Option<Style>,
+    align: (Align, Align),
+}
+
+impl<'a> Text<'a> {
+    pub fn new(lines: Vec<Line<'a>>) -> Text<'a> {
+        Text {
+            lines,
+            style: None,
+            align: (Align::Start, Align::Start),
+        }
+    }
+
+    /// Unstyled text, split into lines at newlines.
+    pub fn raw(s: &'a str) -> Text<'a> {
+        Text::new(s.split('\n').map(Line::from).collect())
+    }
+
+    /// Parses text with markup, see `parse_markup`.
+    #[inline]
+    pub fn markup(s: &'a str) -> Result<Text<'a>, MarkupError> {
+        parse_markup(s)
+    }
+
+    /// The style the spans are put on top of. Defaults to the `base` style of the theme.
+    #[inline]
+    pub fn style(mut self, style: Style) -> Text<'a> {
+        self.style = Some(style);
+        self
+    }
+
+    /// Places the text horizontally and vertically in the area it is drawn in.
+    #[inline]
+    pub fn align(mut self, horizontal: Align, vertical: Align) -> Text<'a> {
+        self.align = (horizontal, vertical);
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.lines.iter().map(Line::width).max().unwrap_or(0)
+    }
+
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.lines.len()
+    }
+}
+
+impl<'a> From<Line<'a>> for Text<'a> {
+    fn from(line: Line<'a>) -> Text<'a> {
+        Text::new(vec![line])
+    }
+}
+
+impl<'a> Widget for Text<'a> {
+    fn draw_on(&self, ctx: &mut DrawingContext) {
+        let style = ctx.themed(self.style, roles::BASE);
+        let size = ctx.size();
+        ctx.aligned_text((0, 0), size, &self.lines, self.align, style);
+    }
+}
+
+/// Error parsing markup, at a byte offset into the source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MarkupError {
+    pub offset: usize,
+    pub message: String,
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+impl Error for MarkupError {}
+
+/// The name attribute tags are closed by.
+fn short_name(name: &str) -> &str {
+    match name {
+        "bold" => "b",
+        "underline" => "u",
+        "reverse" => "r",
+        _ => name,
+    }
+}
+
+/// Parses the contents of an opening tag, returning the name it is closed by and its style.
+fn parse_tag(tag: &str) -> Result<(&str, StyleSpec), String> {
+    let (name, spec) = match short_name(tag) {
+        "b" => ("b", "bold"),
+        "u" => ("u", "underline"),
+        "r" => ("r", "reverse"),
+        _ if tag.starts_with("fg=") => ("fg", &tag[3..]),
+        _ if tag.starts_with("bg=") => ("bg", &tag[3..]),
+        _ => ("", tag),
+    };
+
+    let style = match name {
+        "bg" => parse_style(&format!("on {}", spec))?,
+        _ => parse_style(spec)?,
+    };
+    Ok((name, style))
+}
+
+#[inline]
+fn current_style(stack: &[(&str, StyleSpec)]) -> StyleSpec {
+    stack.last().map_or(StyleSpec::new(), |&(_, style)| style)
+}
+
+fn push_span<'a>(line: &mut Line<'a>, text: &'a str, stack: &[(&str, StyleSpec)]) {
+    if !text.is_empty() {
+        line.spans.push(Span::styled(text, current_style(stack)));
+    }
+}
+
+/// Parses text with markup into styled lines.
+///
+/// Tags are put in square brackets: `[b]`, `[u]` and `[r]` (or `[bold]`, `[underline]` and
+/// `[reverse]`) add attributes, `[fg=red]` and `[bg=#303030]` set colors, and any other tag is
+/// read as style like `[bold yellow on blue]`. Tags nest and are closed in reverse order by
+/// `[/]`, or by their name as in `[/b]` and `[/fg]`. Tags still open at the end are closed
+/// implicitly. `[[` stands for a literal bracket.
+pub fn parse_markup(s: &str) -> Result<Text<'_>, MarkupError> {
+    let err = |offset, message: String| MarkupError { offset, message };
+
+    // open tags with the styles in effect inside of them
+    let mut stack: Vec<(&str, StyleSpec)> = Vec::new();
+    let mut lines = Vec::new();
+    let mut line = Line::default();
+    let mut start = 0;
+    let mut pos = 0;
+
+    while let Some(i) = s[pos..].find(['[', '\n']).map(|i| pos + i) {
+        if s.as_bytes()[i] == b'\n' {
+            push_span(&mut line, &s[start..i], &stack);
+            lines.push(line);
+            line = Line::default();
+            start = i + 1;
+            pos = start;
+            continue;
+        }
+
+        if s[i + 1..].starts_with('[') {
+            // keep the first bracket, skip the second
+            push_span(&mut line, &s[start..i + 1], &stack);
+            start = i + 2;
+            pos = start;
+            continue;
+        }
+
+        let end = match s[i..].find([']', '\n']).map(|end| i + end) {
+            Some(end) if s.as_bytes()[end] == b']' => end,
+            _ => return Err(err(i, "unterminated tag".to_owned())),
+        };
+        let tag = s[i + 1..end].trim();
+        push_span(&mut line, &s[start..i], &stack);
+
+        if let Some(name) = tag.strip_prefix('/') {
+            let name = name.trim();
+            match stack.pop() {
+                Some((open, _)) if name.is_empty() || short_name(name) == open => (),
+                Some(_) => {
+                    return Err(err(i, format!("[{}] does not close the innermost tag", tag)));
+                }
+                None => return Err(err(i, format!("[{}] without an open tag", tag))),
+            }
+        } else {
+            let (name, style) = parse_tag(tag).map_err(|message| err(i, message))?;
+            let outer = current_style(&stack);
+            stack.push((name, outer.patch(style)));
+        }
+
+        start = end + 1;
+        pos = start;
+    }
+
+    push_span(&mut line, &s[start..], &stack);
+    lines.push(line);
+    Ok(Text::new(lines))
+}
@@ -0,0 +1,372 @@
+use rustty::Attr;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use super::{Color, DEFAULT_STYLE, Style};
+
+/// Roles of the built-in widgets.
+///
+/// Roles are hierarchical, separated by dots: `list.cursor` inherits everything it leaves
+/// unset from `list`, which in turn inherits from the style of the container it is drawn in,
+/// and finally from `base`.
+pub mod roles {
+    pub const BASE: &str = "base";
+
+    pub const BACKGROUND: &str = "background";
+    pub const WINDOW: &str = "window";
+    pub const WINDOW_FRAME: &str = "window.frame";
+
+    pub const BUTTON: &str = "button";
+    pub const BUTTON_ACTIVE: &str = "button.active";
+    pub const BUTTON_HOTKEY: &str = "button.hotkey";
+
+    pub const CHECKBOX: &str = "checkbox";
+    pub const CHECKBOX_FOCUSED: &str = "checkbox.focused";
+
+    pub const RADIO: &str = "radio";
+    pub const RADIO_SELECTED: &str = "radio.selected";
+    pub const RADIO_FOCUSED: &str = "radio.selected.focused";
+
+    pub const TOGGLE: &str = "toggle";
+    pub const TOGGLE_ON: &str = "toggle.on";
+    pub const TOGGLE_OFF: &str = "toggle.off";
+    pub const TOGGLE_FOCUSED: &str = "toggle.focused";
+
+    pub const LIST: &str = "list";
+    pub const LIST_CURSOR: &str = "list.cursor";
+    pub const LIST_MARKED: &str = "list.marked";
+    pub const LIST_MATCH: &str = "list.match";
+
+    pub const PALETTE: &str = "palette";
+    pub const PALETTE_PROMPT: &str = "palette.prompt";
+    pub const PALETTE_CURSOR: &str = "palette.cursor";
+    pub const PALETTE_MATCH: &str = "palette.match";
+
+    pub const PROGRESS_FILLED: &str = "progress.filled";
+    pub const PROGRESS_EMPTY: &str = "progress.empty";
+    pub const PROGRESS_LABEL: &str = "progress.label";
+    pub const SPINNER: &str = "spinner";
+
+    pub const TABLE_HEADER: &str = "table.header";
+    pub const TABLE_CELL: &str = "table.cell";
+    pub const TABLE_SELECTED: &str = "table.selected";
+
+    pub const TERMINAL: &str = "terminal";
+
+    pub const CANVAS: &str = "canvas";
+
+    pub const CHART: &str = "chart";
+    pub const CHART_AXIS: &str = "chart.axis";
+    pub const CHART_LABEL: &str = "chart.label";
+    pub const CHART_BAR: &str = "chart.bar";
+    pub const HEATMAP: &str = "heatmap";
+
+    pub const HELP: &str = "help";
+    pub const HELP_KEYS: &str = "help.keys";
+    pub const HELP_HEADING: &str = "help.heading";
+
+    pub const MESSAGE: &str = "message";
+}
+
+/// A style whose parts may be left unset, to be inherited from the parent role.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StyleSpec {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub attr: Option<Attr>,
+}
+
+impl StyleSpec {
+    pub fn new() -> StyleSpec {
+        StyleSpec::default()
+    }
+
+    /// A style clearing inherited attributes, like `plain` in `parse_style`.
+    #[inline]
+    pub fn plain() -> StyleSpec {
+        StyleSpec::new().attr(Attr::Default)
+    }
+
+    #[inline]
+    pub fn fg(mut self, fg: Color) -> StyleSpec {
+        self.fg = Some(fg);
+        self
+    }
+
+    #[inline]
+    pub fn bg(mut self, bg: Color) -> StyleSpec {
+        self.bg = Some(bg);
+        self
+    }
+
+    #[inline]
+    pub fn attr(mut self, attr: Attr) -> StyleSpec {
+        self.attr = Some(attr);
+        self
+    }
+
+    /// Overrides the parts of `style` set in `self`.
+    #[inline]
+    pub fn apply(&self, style: Style) -> Style {
+        (self.fg.unwrap_or(style.0), self.bg.unwrap_or(style.1), self.attr.unwrap_or(style.2))
+    }
+
+    /// Puts `other` on top: its colors take precedence, while attributes are added up. If
+    /// `other` is explicitly plain, it clears the attributes instead.
+    pub fn patch(&self, other: StyleSpec) -> StyleSpec {
+        let attr = match (self.attr, other.attr) {
+            (_, Some(Attr::Default)) => Some(Attr::Default),
+            (Some(a), Some(b)) => Some(attr_from_bits(a as u8 | b as u8)),
+            (a, b) => b.or(a),
+        };
+
+        StyleSpec {
+            fg: other.fg.or(self.fg),
+            bg: other.bg.or(self.bg),
+            attr,
+        }
+    }
+}
+
+/// Sets both colors, and the attributes unless there are none: those are left unset to keep
+/// inherited ones. Use `StyleSpec::plain` to clear them.
+impl From<Style> for StyleSpec {
+    fn from(style: Style) -> StyleSpec {
+        StyleSpec {
+            fg: Some(style.0),
+            bg: Some(style.1),
+            attr: if style.2 == Attr::Default {
+                None
+            } else {
+                Some(style.2)
+            },
+        }
+    }
+}
+
+/// Styles for named roles, with parts unset in a role cascading down from its parents.
+///
+/// Built-in widgets take the styles not set explicitly on them from the theme of the
+/// `DrawingContext`, see `Application::theme`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    rules: HashMap<String, StyleSpec>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Theme {
+    /// Creates the built-in theme, which only uses attributes and the default colors.
+    pub fn new() -> Theme {
+        let emphasized = StyleSpec::new().attr(Attr::Bold);
+        let highlighted = StyleSpec::new().attr(Attr::Reverse);
+        let matched = StyleSpec::new().attr(Attr::Underline);
+
+        let mut theme = Theme::empty();
+        theme.set(roles::BUTTON_ACTIVE, highlighted);
+        theme.set(roles::BUTTON_HOTKEY, emphasized);
+        theme.set(roles::CHECKBOX_FOCUSED, highlighted);
+        theme.set(roles::RADIO_SELECTED, emphasized);
+        theme.set(roles::RADIO_FOCUSED, highlighted);
+        theme.set(roles::TOGGLE_ON, emphasized);
+        theme.set(roles::TOGGLE_FOCUSED, highlighted);
+        theme.set(roles::LIST_CURSOR, highlighted);
+        theme.set(roles::LIST_MARKED, emphasized);
+        theme.set(roles::LIST_MATCH, matched);
+        theme.set(roles::PALETTE_PROMPT, emphasized);
+        theme.set(roles::PALETTE_CURSOR, highlighted);
+        theme.set(roles::PALETTE_MATCH, matched);
+        theme.set(roles::TABLE_HEADER, emphasized);
+        theme.set(roles::TABLE_SELECTED, highlighted);
+        theme.set(roles::HELP_KEYS, emphasized);
+        theme.set(roles::HELP_HEADING, matched);
+        theme
+    }
+
+    /// Creates a theme without any rules.
+    pub fn empty() -> Theme {
+        Theme { rules: HashMap::new() }
+    }
+
+    /// The built-in theme, shared by all contexts not given another one.
+    pub fn builtin() -> &'static Theme {
+        static BUILTIN: OnceLock<Theme> = OnceLock::new();
+        BUILTIN.get_or_init(Theme::new)
+    }
+
+    /// Sets the rule for `role`, replacing an existing one. A full `Style` can be passed to
+    /// set the colors and attributes at once, see `From<Style>` for `StyleSpec`.
+    #[inline]
+    pub fn set<S: Into<StyleSpec>>(&mut self, role: &str, spec: S) {
+        self.rules.insert(role.to_owned(), spec.into());
+    }
+
+    /// The rule set for `role` itself, without inherited parts.
+    #[inline]
+    pub fn rule(&self, role: &str) -> Option<StyleSpec> {
+        self.rules.get(role).cloned()
+    }
+
+    /// Resolves the style of `role`, starting from the `base` role and applying the rules of
+    /// each parent in turn.
+    #[inline]
+    pub fn style(&self, role: &str) -> Style {
+        self.style_within(role, StyleSpec::new())
+    }
+
+    /// Resolves the style of `role` for a widget drawn inside of a container, which passes
+    /// `inherited` down to it. The container style goes between `base` and the role.
+    pub fn style_within(&self, role: &str, inherited: StyleSpec) -> Style {
+        let mut style = DEFAULT_STYLE;
+        if let Some(base) = self.rules.get(roles::BASE) {
+            style = base.apply(style);
+        }
+        style = inherited.apply(style);
+
+        let mut end = 0;
+        while end < role.len() {
+            end = role[end..].find('.').map_or(role.len(), |i| end + i);
+            if let Some(rule) = self.rules.get(&role[..end]) {
+                style = rule.apply(style);
+            }
+            end += 1;
+        }
+
+        style
+    }
+
+    /// Adds all rules of `other`, replacing those for the same roles.
+    pub fn merge(&mut self, other: &Theme) {
+        for (role, spec) in &other.rules {
+            self.rules.insert(role.clone(), *spec);
+        }
+    }
+}
+
+fn parse_color(word: &str) -> Result<Color, String> {
+    Ok(match word {
+        "default" => Color::Default,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "white" => Color::White,
+        _ if word.starts_with('#') => {
+            let hex = &word[1..];
+            let value = match u32::from_str_radix(hex, 16) {
+                Ok(value) if hex.len() == 6 && !hex.starts_with('+') => value,
+                _ => return Err(format!("invalid RGB color {:?}", word)),
+            };
+            Color::Rgb((value >> 16) as u8, (value >> 8) as u8, value as u8)
+        }
+        _ => {
+            match word.parse::<u8>() {
+                Ok(n) => Color::Byte(n),
+                Err(_) => return Err(format!("unknown color {:?}", word)),
+            }
+        }
+    })
+}
+
+pub(crate) fn attr_from_bits(bits: u8) -> Attr {
+    match bits {
+        0b001 => Attr::Bold,
+        0b010 => Attr::Underline,
+        0b011 => Attr::BoldUnderline,
+        0b100 => Attr::Reverse,
+        0b101 => Attr::BoldReverse,
+        0b110 => Attr::UnderlineReverse,
+        0b111 => Attr::BoldReverseUnderline,
+        _ => Attr::Default,
+    }
+}
+
+/// Parses a style written like `bold yellow on blue`.
+///
+/// A style consists of any of the attributes `bold`, `underline` and `reverse` (or `plain` to
+/// clear inherited ones), followed by an optional foreground color and `on` with a background
+/// color. Colors are given by name, as number of the 256-color palette or in RGB notation like
+/// `#ff8700`. Omitted parts are left unset.
+pub fn parse_style(s: &str) -> Result<StyleSpec, String> {
+    let mut style = StyleSpec::new();
+    let mut attrs = None;
+    let mut words = s.split_whitespace().peekable();
+
+    while let Some(&word) = words.peek() {
+        let bit = match word {
+            "plain" => 0,
+            "bold" => Attr::Bold as u8,
+            "underline" => Attr::Underline as u8,
+            "reverse" => Attr::Reverse as u8,
+            _ => break,
+        };
+        attrs = Some(attrs.unwrap_or(0) | bit);
+        words.next();
+    }
+    style.attr = attrs.map(attr_from_bits);
+
+    if let Some(word) = words.next() {
+        if word != "on" {
+            style.fg = Some(parse_color(word)?);
+            match words.next() {
+                Some("on") => (),
+                Some(other) => return Err(format!("expected \"on\", found {:?}", other)),
+                None => return Ok(style),
+            }
+        }
+
+        match words.next() {
+            Some(bg) => style.bg = Some(parse_color(bg)?),
+            None => return Err("missing background color after \"on\"".to_owned()),
+        }
+    }
+
+    match words.next() {
+        Some(extra) => Err(format!("unexpected {:?}", extra)),
+        None => Ok(style),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustty::Attr;
+    use super::{parse_style, roles, StyleSpec};
+    use view::{Buffer, Color, DrawingContext};
+
+    #[test]
+    fn patch_attributes() {
+        let bold = parse_style("bold").unwrap();
+        let underline = parse_style("underline red").unwrap();
+        let plain = parse_style("plain").unwrap();
+
+        assert_eq!(bold.patch(underline).attr, Some(Attr::BoldUnderline));
+        assert_eq!(bold.patch(plain).attr, Some(Attr::Default));
+        assert_eq!(bold.patch(parse_style("on blue").unwrap()).attr, Some(Attr::Bold));
+        assert_eq!(plain.patch(bold).attr, Some(Attr::Bold));
+        assert_eq!(bold.patch(StyleSpec::plain()).attr, Some(Attr::Default));
+    }
+
+    #[test]
+    fn full_style_keeps_inherited_attributes() {
+        let bold = parse_style("bold").unwrap();
+        let blue = StyleSpec::from((Color::Blue, Color::Default, Attr::Default));
+        let underlined = StyleSpec::from((Color::Red, Color::Blue, Attr::Underline));
+
+        let expected = StyleSpec::new().fg(Color::Blue).bg(Color::Default).attr(Attr::Bold);
+        assert_eq!(bold.patch(blue), expected);
+        assert_eq!(bold.patch(underlined).attr, Some(Attr::BoldUnderline));
+
+        // the same for styles passed down by containers
+        let mut buffer = Buffer::new((1, 1));
+        let mut ctx = DrawingContext::new(&mut buffer);
+        ctx.inherit(bold);
+        ctx.inherit(blue);
+        assert_eq!(ctx.themed(None, roles::LIST), (Color::Blue, Color::Default, Attr::Bold));
+    }
+}
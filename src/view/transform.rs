@@ -1,14 +1,16 @@
 use rustty::{Pos, Size};
 use super::{DrawingContext, Widget};
+use super::state::Key;
+use super::theme::StyleSpec;
 
 
 pub struct FixedSize<'a> {
     size: Size,
-    widget: Box<Widget + 'a>,
+    widget: Box<dyn Widget + 'a>,
 }
 
 impl<'a> FixedSize<'a> {
-    pub fn new(s: Size, w: Box<Widget + 'a>) -> FixedSize<'a> {
+    pub fn new(s: Size, w: Box<dyn Widget + 'a>) -> FixedSize<'a> {
         FixedSize {
             size: s,
             widget: w,
@@ -39,11 +41,11 @@ impl<'a> Widget for FixedSize<'a> {
 
 pub struct Translated<'a> {
     offset: Pos,
-    widget: Box<Widget + 'a>,
+    widget: Box<dyn Widget + 'a>,
 }
 
 impl<'a> Translated<'a> {
-    fn new(o: Pos, w: Box<Widget + 'a>) -> Translated<'a> {
+    fn new(o: Pos, w: Box<dyn Widget + 'a>) -> Translated<'a> {
         Translated {
             offset: o,
             widget: w,
@@ -60,6 +62,74 @@ impl<'a> Widget for Translated<'a> {
     }
 }
 
+/// Skips redrawing `widget` as long as `generation` stays the same, keeping its output from the
+/// previous frame. Bump the generation whenever the widget's content changes.
+pub struct Memo<'a> {
+    generation: u64,
+    widget: Box<dyn Widget + 'a>,
+}
+
+impl<'a> Memo<'a> {
+    pub fn new(generation: u64, w: Box<dyn Widget + 'a>) -> Memo<'a> {
+        Memo {
+            generation,
+            widget: w,
+        }
+    }
+}
+
+impl<'a> Widget for Memo<'a> {
+    fn draw_on(&self, ctx: &mut DrawingContext) {
+        let widget = &self.widget;
+        ctx.memoize(self.generation, |ctx| widget.draw_on(ctx));
+    }
+}
+
+/// Draws `widget` under an explicit key, giving it an identity for its retained state that
+/// does not depend on its place in the widget tree.
+pub struct Keyed<'a> {
+    key: Key,
+    widget: Box<dyn Widget + 'a>,
+}
+
+impl<'a> Keyed<'a> {
+    pub fn new<K: Into<Key>>(key: K, w: Box<dyn Widget + 'a>) -> Keyed<'a> {
+        Keyed {
+            key: key.into(),
+            widget: w,
+        }
+    }
+}
+
+impl<'a> Widget for Keyed<'a> {
+    fn draw_on(&self, ctx: &mut DrawingContext) {
+        let widget = &self.widget;
+        ctx.keyed(self.key.clone(), |ctx| widget.draw_on(ctx));
+    }
+}
+
+/// Passes `spec` down to `widget` and its children, on top of the style they inherit. Parts
+/// of the style unset in their roles are taken from it.
+pub struct Styled<'a> {
+    spec: StyleSpec,
+    widget: Box<dyn Widget + 'a>,
+}
+
+impl<'a> Styled<'a> {
+    pub fn new(spec: StyleSpec, w: Box<dyn Widget + 'a>) -> Styled<'a> {
+        Styled { spec, widget: w }
+    }
+}
+
+impl<'a> Widget for Styled<'a> {
+    fn draw_on(&self, ctx: &mut DrawingContext) {
+        ctx.save();
+        ctx.inherit(self.spec);
+        self.widget.draw_on(ctx);
+        ctx.restore();
+    }
+}
+
 // FIXME: not working
 // trait MoveSize {
 //     fn offset(self, offset: Pos) -> Box<Widget>;
@@ -75,10 +145,24 @@ impl<'a> Widget for Translated<'a> {
 //     }
 // }
 
-pub fn offset<'a>(offset: Pos, widget: Box<Widget + 'a>) -> Box<Widget + 'a> {
+pub fn offset<'a>(offset: Pos, widget: Box<dyn Widget + 'a>) -> Box<dyn Widget + 'a> {
     Box::new(Translated::new(offset, widget))
 }
 
-pub fn sized<'a>(size: Size, widget: Box<Widget + 'a>) -> Box<Widget + 'a> {
+pub fn sized<'a>(size: Size, widget: Box<dyn Widget + 'a>) -> Box<dyn Widget + 'a> {
     Box::new(FixedSize::new(size, widget))
 }
+
+pub fn memo<'a>(generation: u64, widget: Box<dyn Widget + 'a>) -> Box<dyn Widget + 'a> {
+    Box::new(Memo::new(generation, widget))
+}
+
+pub fn keyed<'a, K: Into<Key>>(key: K, widget: Box<dyn Widget + 'a>) -> Box<dyn Widget + 'a> {
+    Box::new(Keyed::new(key, widget))
+}
+
+pub fn styled<'a, S>(spec: S, widget: Box<dyn Widget + 'a>) -> Box<dyn Widget + 'a>
+    where S: Into<StyleSpec>
+{
+    Box::new(Styled::new(spec.into(), widget))
+}
@@ -1,24 +1,48 @@
-use rustty::Cell;
-use std::ascii::AsciiExt;
 use std::cmp::min;
-use super::{DEFAULT_STYLE, DrawingContext, Style, Widget};
-
-
-pub struct FramedWindow {
-    bg_cell: Cell,
-    frame_cell: Cell,
+use super::{Cell, DrawingContext, LineStyle, Style, Widget};
+use super::theme::{roles, StyleSpec};
+
+
+/// A framed area, with optional content drawn inside of the frame.
+///
+/// Cells not given explicitly are taken from the `window` and `window.frame` roles. The
+/// content inherits the style of the window, e.g. its colors.
+#[derive(Default)]
+pub struct FramedWindow<'a> {
+    bg_cell: Option<Cell>,
+    frame_cell: Option<Cell>,
+    content: Option<Box<dyn Widget + 'a>>,
 }
 
-impl FramedWindow {
-    pub fn new(bg_cell: Cell, frame_cell: Cell) -> FramedWindow {
+impl<'a> FramedWindow<'a> {
+    pub fn new(bg_cell: Cell, frame_cell: Cell) -> FramedWindow<'a> {
         FramedWindow {
-            bg_cell: bg_cell,
-            frame_cell: frame_cell,
+            bg_cell: Some(bg_cell),
+            frame_cell: Some(frame_cell),
+            content: None,
         }
     }
+
+    #[inline]
+    pub fn bg_cell(mut self, bg_cell: Cell) -> FramedWindow<'a> {
+        self.bg_cell = Some(bg_cell);
+        self
+    }
+
+    #[inline]
+    pub fn frame_cell(mut self, frame_cell: Cell) -> FramedWindow<'a> {
+        self.frame_cell = Some(frame_cell);
+        self
+    }
+
+    #[inline]
+    pub fn content(mut self, content: Box<dyn Widget + 'a>) -> FramedWindow<'a> {
+        self.content = Some(content);
+        self
+    }
 }
 
-impl Widget for FramedWindow {
+impl<'a> Widget for FramedWindow<'a> {
     fn draw_on(&self, ctx: &mut DrawingContext) {
         let csize = ctx.size();
 
@@ -26,41 +50,77 @@ impl Widget for FramedWindow {
             return;
         }
 
+        let bg_cell = self.bg_cell.unwrap_or_else(|| {
+            let style = ctx.themed(None, roles::WINDOW);
+            Cell::new(' ', style.0, style.1, style.2)
+        });
+        ctx.fill(bg_cell);
+
         let top = 0;
         let right = csize.0 - 1;
         let bottom = csize.1 - 1;
         let left = 0;
 
-        ctx.fill(self.bg_cell);
-
         // draw border
-        ctx.line((left, top), (right, top), self.frame_cell);
-        ctx.line((right, top), (right, bottom), self.frame_cell);
-        ctx.line((right, bottom), (left, bottom), self.frame_cell);
-        ctx.line((left, bottom), (left, top), self.frame_cell);
+        match self.frame_cell {
+            Some(frame_cell) => {
+                ctx.line((left, top), (right, top), frame_cell);
+                ctx.line((right, top), (right, bottom), frame_cell);
+                ctx.line((right, bottom), (left, bottom), frame_cell);
+                ctx.line((left, bottom), (left, top), frame_cell);
+            }
+            None => {
+                ctx.save();
+                ctx.inherit(cell_style(bg_cell));
+                let style = ctx.themed(None, roles::WINDOW_FRAME);
+                ctx.restore();
+                ctx.rect((0, 0), csize, LineStyle::Light, style);
+            }
+        }
+
+        if let Some(ref content) = self.content {
+            ctx.save();
+            ctx.translate((1, 1));
+            ctx.shrink((1, 1));
+            ctx.inherit(cell_style(bg_cell));
+            ctx.keyed(0, |ctx| content.draw_on(ctx));
+            ctx.restore();
+        }
     }
 }
 
+/// The style of `cell`, as passed down to the content drawn on top of it.
+#[inline]
+fn cell_style(cell: Cell) -> StyleSpec {
+    StyleSpec::from((cell.fg(), cell.bg(), cell.attrs()))
+}
+
 
 pub struct MessageFill<'a> {
-    bg_cell: Cell,
+    bg_cell: Option<Cell>,
     msg: &'a str,
     padding: usize,
-    style: Style,
+    style: Option<Style>,
+}
+
+impl<'a> Default for MessageFill<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<'a> MessageFill<'a> {
     pub fn new() -> MessageFill<'a> {
         MessageFill {
-            bg_cell: Cell::new(' ', DEFAULT_STYLE.0, DEFAULT_STYLE.1, DEFAULT_STYLE.2),
+            bg_cell: None,
             msg: "",
             padding: 2,
-            style: DEFAULT_STYLE,
+            style: None,
         }
     }
 
     pub fn bg_cell(mut self, bg_cell: Cell) -> MessageFill<'a> {
-        self.bg_cell = bg_cell;
+        self.bg_cell = Some(bg_cell);
         self
     }
 
@@ -75,7 +135,7 @@ impl<'a> MessageFill<'a> {
     }
 
     pub fn style(mut self, style: Style) -> MessageFill<'a> {
-        self.style = style;
+        self.style = Some(style);
         self
     }
 }
@@ -88,6 +148,12 @@ impl<'a> Widget for MessageFill<'a> {
 
         let (cols, rows) = ctx.size();
 
+        let bg_cell = self.bg_cell.unwrap_or_else(|| {
+            let style = ctx.themed(None, roles::MESSAGE);
+            Cell::new(' ', style.0, style.1, style.2)
+        });
+        ctx.fill(bg_cell);
+
         if cols <= self.padding * 2 || rows < 1 {
             return;
         }
@@ -95,13 +161,17 @@ impl<'a> Widget for MessageFill<'a> {
         let tx_row_len = cols - 2 * self.padding;
 
         let tx_rows = self.msg.len() % tx_row_len;
+        ctx.save();
+        ctx.inherit(cell_style(bg_cell));
+        let style = ctx.themed(self.style, roles::MESSAGE);
+        ctx.restore();
 
         for n in 0..tx_rows {
             let start = min(n * tx_row_len, self.msg.len());
             let end = min(self.msg.len(), start + tx_row_len);
             ctx.text((tx_row_len / 2 + self.padding, rows.saturating_sub(tx_rows) / 2 + n),
                      &self.msg[start..end],
-                     self.style)
+                     style)
         }
 
     }
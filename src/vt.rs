@@ -0,0 +1,738 @@
+//! A VT100/xterm-compatible terminal, kept in memory.
+//!
+//! The `Emulator` interprets the output of programs the way a terminal does: cursor movement,
+//! erasing, scrolling regions, the alternate screen and SGR colors and attributes. Lines
+//! scrolled off the top of the main screen are kept as scrollback.
+
+use rustty::{Pos, Size};
+use std::cmp::{max, min};
+use std::collections::VecDeque;
+use std::mem;
+use view::ansi::{Csi, Perform, Sgr, Tokenizer};
+use view::theme::StyleSpec;
+
+/// Default number of lines kept as scrollback.
+const DEFAULT_SCROLLBACK: usize = 1000;
+
+const TAB_WIDTH: usize = 8;
+
+/// A character on the screen, styled on top of the style the terminal is drawn with.
+pub type VtCell = (char, StyleSpec);
+
+/// Whether `ch` is known to take up a single column. Others are shown as `?`.
+#[inline]
+fn is_narrow(ch: char) -> bool {
+    // Latin scripts, box drawing and block elements
+    (' '..'\u{300}').contains(&ch) || ('\u{2500}'..='\u{259f}').contains(&ch)
+}
+
+/// Line drawing characters of the DEC special graphics set, starting at `` ` ``.
+const DEC_GRAPHICS: [char; 31] = ['+', '\u{2592}', '\u{2409}', '\u{240c}', '\u{240d}', '\u{240a}',
+                                  '\u{b0}', '\u{b1}', '\u{2424}', '\u{240b}', '\u{2518}',
+                                  '\u{2510}', '\u{250c}', '\u{2514}', '\u{253c}', '-', '-',
+                                  '\u{2500}', '-', '_', '\u{251c}', '\u{2524}', '\u{2534}',
+                                  '\u{252c}', '\u{2502}', '<', '>', 'n', '=', '#', '\u{b7}'];
+
+#[derive(Clone, Copy, Debug, Default)]
+struct SavedCursor {
+    pos: Pos,
+    sgr: Sgr,
+    origin: bool,
+    graphics: [bool; 2],
+}
+
+#[derive(Clone, Debug)]
+struct Screen {
+    size: Size,
+    rows: Vec<Vec<VtCell>>,
+    // main screen rows while the alternate screen is shown
+    main: Option<Vec<Vec<VtCell>>>,
+    scrollback: VecDeque<Vec<VtCell>>,
+    max_scrollback: usize,
+
+    cursor: Pos,
+    // the last column was written, the next character goes to the next line
+    wrap_pending: bool,
+    saved: SavedCursor,
+    sgr: Sgr,
+    // the scrolling region, both ends included
+    top: usize,
+    bottom: usize,
+
+    autowrap: bool,
+    origin: bool,
+    insert: bool,
+    cursor_visible: bool,
+    app_cursor: bool,
+    // whether G0 and G1 are the line drawing set, and which of them is used
+    graphics: [bool; 2],
+    shifted: bool,
+
+    response: Vec<u8>,
+}
+
+impl Screen {
+    fn new(size: Size) -> Screen {
+        let (cols, rows) = size;
+        Screen {
+            size,
+            rows: vec![vec![(' ', StyleSpec::new()); cols]; rows],
+            main: None,
+            scrollback: VecDeque::new(),
+            max_scrollback: DEFAULT_SCROLLBACK,
+            cursor: (0, 0),
+            wrap_pending: false,
+            saved: SavedCursor::default(),
+            sgr: Sgr::default(),
+            top: 0,
+            bottom: rows.saturating_sub(1),
+            autowrap: true,
+            origin: false,
+            insert: false,
+            cursor_visible: true,
+            app_cursor: false,
+            graphics: [false; 2],
+            shifted: false,
+            response: Vec::new(),
+        }
+    }
+
+    /// A blank cell, taking the current background color.
+    #[inline]
+    fn blank(&self) -> VtCell {
+        (' ',
+         StyleSpec {
+             bg: self.sgr.bg,
+             ..StyleSpec::new()
+         })
+    }
+
+    #[inline]
+    fn blank_row(&self) -> Vec<VtCell> {
+        vec![self.blank(); self.size.0]
+    }
+
+    fn move_to(&mut self, x: usize, y: usize) {
+        let (cols, rows) = self.size;
+        self.cursor = (min(x, cols.saturating_sub(1)), min(y, rows.saturating_sub(1)));
+        self.wrap_pending = false;
+    }
+
+    /// Moves to a position given relative to the scrolling region in origin mode.
+    fn move_to_origin(&mut self, x: usize, y: usize) {
+        if self.origin {
+            let y = min(self.top + y, self.bottom);
+            self.move_to(x, y);
+        } else {
+            self.move_to(x, y);
+        }
+    }
+
+    /// Scrolls the lines from `top` to `bottom` up by `n`, keeping lines scrolled off the top
+    /// of the main screen.
+    fn scroll_up(&mut self, top: usize, bottom: usize, n: usize) {
+        if top > bottom || bottom >= self.rows.len() {
+            return;
+        }
+        let n = min(n, bottom + 1 - top);
+        let keep = top == 0 && self.main.is_none();
+
+        for _ in 0..n {
+            let row = self.rows.remove(top);
+            let blank = self.blank_row();
+            self.rows.insert(bottom, blank);
+
+            if keep && self.max_scrollback > 0 {
+                if self.scrollback.len() == self.max_scrollback {
+                    self.scrollback.pop_front();
+                }
+                self.scrollback.push_back(row);
+            }
+        }
+    }
+
+    fn scroll_down(&mut self, top: usize, bottom: usize, n: usize) {
+        if top > bottom || bottom >= self.rows.len() {
+            return;
+        }
+        let n = min(n, bottom + 1 - top);
+        for _ in 0..n {
+            self.rows.remove(bottom);
+            let blank = self.blank_row();
+            self.rows.insert(top, blank);
+        }
+    }
+
+    fn linefeed(&mut self) {
+        let (x, y) = self.cursor;
+        if y == self.bottom {
+            let (top, bottom) = (self.top, self.bottom);
+            self.scroll_up(top, bottom, 1);
+        } else if y + 1 < self.size.1 {
+            self.move_to(x, y + 1);
+        }
+        self.wrap_pending = false;
+    }
+
+    fn reverse_index(&mut self) {
+        let (x, y) = self.cursor;
+        if y == self.top {
+            let (top, bottom) = (self.top, self.bottom);
+            self.scroll_down(top, bottom, 1);
+        } else if y > 0 {
+            self.move_to(x, y - 1);
+        }
+    }
+
+    fn erase(&mut self, y: usize, from: usize, to: usize) {
+        let blank = self.blank();
+        let row = &mut self.rows[y];
+        let to = min(to, row.len());
+        for cell in &mut row[min(from, to)..to] {
+            *cell = blank;
+        }
+    }
+
+    fn save_cursor(&mut self) {
+        self.saved = SavedCursor {
+            pos: self.cursor,
+            sgr: self.sgr,
+            origin: self.origin,
+            graphics: self.graphics,
+        };
+    }
+
+    fn restore_cursor(&mut self) {
+        let saved = self.saved;
+        self.sgr = saved.sgr;
+        self.origin = saved.origin;
+        self.graphics = saved.graphics;
+        self.move_to(saved.pos.0, saved.pos.1);
+
+        // the region may have been set after saving, the cursor stays within it in origin mode
+        if self.origin {
+            let (x, y) = self.cursor;
+            let y = min(max(y, self.top), self.bottom);
+            self.move_to(x, y);
+        }
+    }
+
+    fn set_alternate(&mut self, on: bool) {
+        if on && self.main.is_none() {
+            let blank = vec![vec![(' ', StyleSpec::new()); self.size.0]; self.size.1];
+            self.main = Some(mem::replace(&mut self.rows, blank));
+        } else if !on {
+            if let Some(main) = self.main.take() {
+                self.rows = main;
+            }
+        }
+    }
+
+    fn set_mode(&mut self, private: bool, mode: u32, on: bool) {
+        match (private, mode) {
+            (false, 4) => self.insert = on,
+            (true, 1) => self.app_cursor = on,
+            (true, 6) => {
+                self.origin = on;
+                self.move_to_origin(0, 0);
+            }
+            (true, 7) => self.autowrap = on,
+            (true, 25) => self.cursor_visible = on,
+            (true, 47) | (true, 1047) => self.set_alternate(on),
+            (true, 1048) => {
+                if on {
+                    self.save_cursor()
+                } else {
+                    self.restore_cursor()
+                }
+            }
+            (true, 1049) => {
+                if on {
+                    self.save_cursor();
+                    self.set_alternate(true);
+                } else {
+                    self.set_alternate(false);
+                    self.restore_cursor();
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn resize(&mut self, size: Size) {
+        let (cols, rows) = size;
+
+        // keep the cursor line on screen by moving lines above it into the scrollback
+        if rows < self.size.1 && self.cursor.1 >= rows {
+            let n = self.cursor.1 + 1 - rows;
+            let bottom = self.size.1 - 1;
+            self.scroll_up(0, bottom, n);
+            self.cursor.1 -= n;
+        }
+
+        for grid in Some(&mut self.rows).into_iter().chain(self.main.as_mut()) {
+            grid.resize(rows, Vec::new());
+            for row in grid.iter_mut() {
+                row.resize(cols, (' ', StyleSpec::new()));
+            }
+        }
+
+        self.size = size;
+        self.top = 0;
+        self.bottom = rows.saturating_sub(1);
+        let (x, y) = self.cursor;
+        self.move_to(x, y);
+    }
+}
+
+impl Perform for Screen {
+    fn print(&mut self, ch: char) {
+        let (cols, _) = self.size;
+        if cols == 0 || self.size.1 == 0 {
+            return;
+        }
+
+        let ch = match ch {
+            '`'..='~' if self.graphics[self.shifted as usize] => {
+                DEC_GRAPHICS[(ch as u8 - b'`') as usize]
+            }
+            _ if is_narrow(ch) => ch,
+            _ => '?',
+        };
+
+        if self.wrap_pending && self.autowrap {
+            self.linefeed();
+            self.cursor.0 = 0;
+        }
+        self.wrap_pending = false;
+
+        let (x, y) = self.cursor;
+        let cell = (ch, self.sgr.style());
+        let row = &mut self.rows[y];
+        if self.insert {
+            row.insert(x, cell);
+            row.truncate(cols);
+        } else {
+            row[x] = cell;
+        }
+
+        if x + 1 < cols {
+            self.cursor.0 += 1;
+        } else {
+            self.wrap_pending = true;
+        }
+    }
+
+    fn execute(&mut self, byte: u8) {
+        let (x, y) = self.cursor;
+        match byte {
+            0x08 => self.move_to(x.saturating_sub(1), y),
+            b'\t' => {
+                let next = (x / TAB_WIDTH + 1) * TAB_WIDTH;
+                self.move_to(next, y);
+            }
+            b'\n' | 0x0b | 0x0c => self.linefeed(),
+            b'\r' => self.move_to(0, y),
+            0x0e => self.shifted = true,
+            0x0f => self.shifted = false,
+            _ => (),
+        }
+    }
+
+    fn csi(&mut self, csi: &Csi) {
+        let (x, y) = self.cursor;
+        let (cols, rows) = self.size;
+        if cols == 0 || rows == 0 {
+            return;
+        }
+
+        let n = csi.arg(0, 1) as usize;
+        let private = csi.private();
+
+        if csi.intermediate.is_some() {
+            return;
+        }
+
+        match (private, csi.cmd) {
+            (None, b'A') => {
+                let limit = if y >= self.top { self.top } else { 0 };
+                self.move_to(x, max(y.saturating_sub(n), limit));
+            }
+            (None, b'B') | (None, b'e') => {
+                let limit = if y <= self.bottom { self.bottom } else { rows - 1 };
+                self.move_to(x, min(y + n, limit));
+            }
+            (None, b'C') | (None, b'a') => self.move_to(x + n, y),
+            (None, b'D') => self.move_to(x.saturating_sub(n), y),
+            (None, b'E') => {
+                let limit = if y <= self.bottom { self.bottom } else { rows - 1 };
+                self.move_to(0, min(y + n, limit));
+            }
+            (None, b'F') => {
+                let limit = if y >= self.top { self.top } else { 0 };
+                self.move_to(0, max(y.saturating_sub(n), limit));
+            }
+            (None, b'G') | (None, b'`') => self.move_to(n - 1, y),
+            (None, b'H') | (None, b'f') => {
+                let (row, col) = (csi.arg(0, 1) as usize, csi.arg(1, 1) as usize);
+                self.move_to_origin(col - 1, row - 1);
+            }
+            (None, b'd') => self.move_to_origin(x, n - 1),
+            (None, b'J') => {
+                match csi.numbers().first().cloned().unwrap_or(None).unwrap_or(0) {
+                    0 => {
+                        self.erase(y, x, cols);
+                        for row in y + 1..rows {
+                            self.erase(row, 0, cols);
+                        }
+                    }
+                    1 => {
+                        for row in 0..y {
+                            self.erase(row, 0, cols);
+                        }
+                        self.erase(y, 0, x + 1);
+                    }
+                    2 => {
+                        for row in 0..rows {
+                            self.erase(row, 0, cols);
+                        }
+                    }
+                    3 => self.scrollback.clear(),
+                    _ => (),
+                }
+            }
+            (None, b'K') => {
+                match csi.numbers().first().cloned().unwrap_or(None).unwrap_or(0) {
+                    0 => self.erase(y, x, cols),
+                    1 => self.erase(y, 0, x + 1),
+                    2 => self.erase(y, 0, cols),
+                    _ => (),
+                }
+            }
+            (None, b'L') if y >= self.top && y <= self.bottom => {
+                let bottom = self.bottom;
+                self.scroll_down(y, bottom, n);
+                self.move_to(0, y);
+            }
+            (None, b'M') if y >= self.top && y <= self.bottom => {
+                let bottom = self.bottom;
+                // deleted lines do not go into the scrollback
+                let max_scrollback = mem::replace(&mut self.max_scrollback, 0);
+                self.scroll_up(y, bottom, n);
+                self.max_scrollback = max_scrollback;
+                self.move_to(0, y);
+            }
+            (None, b'@') => {
+                let blank = self.blank();
+                let row = &mut self.rows[y];
+                for _ in 0..min(n, cols - x) {
+                    row.insert(x, blank);
+                }
+                row.truncate(cols);
+                self.wrap_pending = false;
+            }
+            (None, b'P') => {
+                let blank = self.blank();
+                let row = &mut self.rows[y];
+                row.drain(x..min(x + n, cols));
+                row.resize(cols, blank);
+                self.wrap_pending = false;
+            }
+            (None, b'X') => self.erase(y, x, x + n),
+            (None, b'S') => {
+                let (top, bottom) = (self.top, self.bottom);
+                self.scroll_up(top, bottom, n);
+            }
+            (None, b'T') => {
+                let (top, bottom) = (self.top, self.bottom);
+                self.scroll_down(top, bottom, n);
+            }
+            (None, b'm') => self.sgr.apply(&csi.numbers()),
+            (None, b'r') => {
+                let top = csi.arg(0, 1) as usize - 1;
+                let bottom = min(csi.arg(1, rows as u32) as usize, rows) - 1;
+                if top < bottom {
+                    self.top = top;
+                    self.bottom = bottom;
+                    self.move_to_origin(0, 0);
+                }
+            }
+            (None, b's') => self.save_cursor(),
+            (None, b'u') => self.restore_cursor(),
+            (None, b'n') => {
+                match csi.arg(0, 0) {
+                    5 => self.response.extend_from_slice(b"\x1b[0n"),
+                    6 => {
+                        let row = if self.origin { y.saturating_sub(self.top) } else { y };
+                        let report = format!("\x1b[{};{}R", row + 1, x + 1);
+                        self.response.extend_from_slice(report.as_bytes());
+                    }
+                    _ => (),
+                }
+            }
+            (None, b'c') => self.response.extend_from_slice(b"\x1b[?1;2c"),
+            (None, b'h') | (None, b'l') | (Some(b'?'), b'h') | (Some(b'?'), b'l') => {
+                for mode in csi.numbers().into_iter().flatten() {
+                    self.set_mode(private.is_some(), mode, csi.cmd == b'h');
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn esc(&mut self, intermediate: Option<u8>, cmd: u8) {
+        match (intermediate, cmd) {
+            (None, b'7') => self.save_cursor(),
+            (None, b'8') => self.restore_cursor(),
+            (None, b'D') => self.linefeed(),
+            (None, b'E') => {
+                self.linefeed();
+                self.cursor.0 = 0;
+            }
+            (None, b'M') => self.reverse_index(),
+            (None, b'c') => {
+                let (size, scrollback, max_scrollback) =
+                    (self.size, mem::take(&mut self.scrollback), self.max_scrollback);
+                *self = Screen::new(size);
+                self.scrollback = scrollback;
+                self.max_scrollback = max_scrollback;
+            }
+            (Some(b'('), set) => self.graphics[0] = set == b'0',
+            (Some(b')'), set) => self.graphics[1] = set == b'0',
+            _ => (),
+        }
+    }
+}
+
+/// A terminal screen, updated with the output of the program running in it.
+#[derive(Clone, Debug)]
+pub struct Emulator {
+    tokenizer: Tokenizer,
+    screen: Screen,
+}
+
+impl Emulator {
+    /// Creates a screen of `size`, which is at least a single cell.
+    pub fn new(size: Size) -> Emulator {
+        Emulator {
+            tokenizer: Tokenizer::new(),
+            screen: Screen::new((max(size.0, 1), max(size.1, 1))),
+        }
+    }
+
+    /// Limits the number of lines kept as scrollback.
+    #[inline]
+    pub fn scrollback(mut self, lines: usize) -> Emulator {
+        self.screen.max_scrollback = lines;
+        self
+    }
+
+    /// Processes output of the program.
+    #[inline]
+    pub fn feed(&mut self, output: &[u8]) {
+        self.tokenizer.feed(output, &mut self.screen);
+    }
+
+    /// Replies to queries, like the cursor position, to be sent back to the program.
+    #[inline]
+    pub fn take_response(&mut self) -> Vec<u8> {
+        mem::take(&mut self.screen.response)
+    }
+
+    #[inline(always)]
+    pub fn size(&self) -> Size {
+        self.screen.size
+    }
+
+    /// Changes the size of the screen. Lines are cut off or padded rather than rewrapped.
+    ///
+    /// Empty sizes are ignored, the screen keeps its previous size.
+    pub fn resize(&mut self, size: Size) {
+        if size.0 > 0 && size.1 > 0 && size != self.screen.size {
+            self.screen.resize(size);
+        }
+    }
+
+    #[inline]
+    pub fn cursor(&self) -> Pos {
+        self.screen.cursor
+    }
+
+    #[inline]
+    pub fn cursor_visible(&self) -> bool {
+        self.screen.cursor_visible
+    }
+
+    /// Whether the program switched to the alternate screen, like full-screen programs do.
+    #[inline]
+    pub fn is_alternate(&self) -> bool {
+        self.screen.main.is_some()
+    }
+
+    /// Whether the program asked for the cursor keys to send `ESC O` sequences.
+    #[inline]
+    pub fn application_cursor(&self) -> bool {
+        self.screen.app_cursor
+    }
+
+    /// Number of lines in the scrollback. The alternate screen has none.
+    #[inline]
+    pub fn scrollback_len(&self) -> usize {
+        if self.is_alternate() {
+            0
+        } else {
+            self.screen.scrollback.len()
+        }
+    }
+
+    /// Row `y` of the screen when scrolled back by `scroll` lines.
+    pub fn row(&self, scroll: usize, y: usize) -> Option<&[VtCell]> {
+        let scrollback = self.scrollback_len();
+        let idx = (scrollback - min(scroll, scrollback)) + y;
+
+        if idx < scrollback {
+            self.screen.scrollback.get(idx).map(|row| &row[..])
+        } else {
+            self.screen.rows.get(idx - scrollback).map(|row| &row[..])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Emulator;
+
+    fn emulator(size: (usize, usize), output: &[u8]) -> Emulator {
+        let mut vt = Emulator::new(size);
+        vt.feed(output);
+        vt
+    }
+
+    fn line(vt: &Emulator, y: usize) -> String {
+        let text: String = vt.row(0, y).unwrap().iter().map(|&(ch, _)| ch).collect();
+        text.trim_end().to_string()
+    }
+
+    fn lines(vt: &Emulator) -> Vec<String> {
+        (0..vt.size().1).map(|y| line(vt, y)).collect()
+    }
+
+    #[test]
+    fn print_and_wrap() {
+        let vt = emulator((4, 3), b"abcdef\r\ng");
+        assert_eq!(lines(&vt), ["abcd", "ef", "g"]);
+        assert_eq!(vt.cursor(), (1, 2));
+    }
+
+    #[test]
+    fn scroll_region_with_origin_mode() {
+        let mut vt = emulator((5, 5), b"0\r\n1\r\n2\r\n3\r\n4");
+        vt.feed(b"\x1b[2;4r\x1b[?6h");
+        assert_eq!(vt.cursor(), (0, 1));
+
+        // positions are relative to the region and clamped to it
+        vt.feed(b"\x1b[2;1H");
+        assert_eq!(vt.cursor(), (0, 2));
+        vt.feed(b"\x1b[9;1H");
+        assert_eq!(vt.cursor(), (0, 3));
+
+        // scrolling at the bottom of the region leaves the lines outside of it alone
+        vt.feed(b"\nx");
+        assert_eq!(lines(&vt), ["0", "2", "3", "x", "4"]);
+        assert_eq!(vt.scrollback_len(), 0);
+
+        vt.feed(b"\x1b[9A");
+        assert_eq!(vt.cursor(), (1, 1));
+        vt.feed(b"\x1b[3B\x1b[9F");
+        assert_eq!(vt.cursor(), (0, 1));
+    }
+
+    #[test]
+    fn cursor_position_report() {
+        let mut vt = emulator((10, 5), b"\x1b[3;4H\x1b[6n");
+        assert_eq!(vt.take_response(), b"\x1b[3;4R");
+        assert!(vt.take_response().is_empty());
+
+        vt.feed(b"\x1b[2;5r\x1b[?6h\x1b[2;3H\x1b[6n");
+        assert_eq!(vt.take_response(), b"\x1b[2;3R");
+
+        vt.feed(b"\x1b[5n");
+        assert_eq!(vt.take_response(), b"\x1b[0n");
+    }
+
+    #[test]
+    fn restore_cursor_above_region_in_origin_mode() {
+        let mut vt = emulator((10, 5), b"\x1b[?6h\x1b7");
+        vt.feed(b"\x1b[3;5r\x1b8");
+        assert_eq!(vt.cursor(), (0, 2));
+
+        vt.feed(b"\x1b[F\x1b[A\x1b[6n");
+        assert_eq!(vt.cursor(), (0, 2));
+        assert_eq!(vt.take_response(), b"\x1b[1;1R");
+    }
+
+    #[test]
+    fn alternate_screen() {
+        let mut vt = emulator((5, 3), b"main\x1b[?1049h");
+        assert!(vt.is_alternate());
+        assert_eq!(lines(&vt), ["", "", ""]);
+
+        vt.feed(b"\x1b[Halt\r\n\n\n\n");
+        assert_eq!(vt.scrollback_len(), 0);
+
+        vt.feed(b"\x1b[?1049l");
+        assert!(!vt.is_alternate());
+        assert_eq!(lines(&vt), ["main", "", ""]);
+        assert_eq!(vt.cursor(), (4, 0));
+    }
+
+    #[test]
+    fn insert_and_delete_lines() {
+        let mut vt = emulator((3, 4), b"a\r\nb\r\nc\r\nd");
+        vt.feed(b"\x1b[2;1H\x1b[L");
+        assert_eq!(lines(&vt), ["a", "", "b", "c"]);
+
+        vt.feed(b"\x1b[2M");
+        assert_eq!(lines(&vt), ["a", "c", "", ""]);
+        assert_eq!(vt.scrollback_len(), 0);
+
+        // lines outside of the scrolling region are not affected
+        vt.feed(b"\x1b[1;2r\x1b[4;1Hx\x1b[L");
+        assert_eq!(lines(&vt), ["a", "c", "", "x"]);
+    }
+
+    #[test]
+    fn resize_keeps_cursor_line() {
+        let mut vt = emulator((4, 4), b"a\r\nb\r\nc\r\nd");
+        vt.resize((2, 2));
+        assert_eq!(lines(&vt), ["c", "d"]);
+        assert_eq!(vt.cursor(), (1, 1));
+        assert_eq!(vt.scrollback_len(), 2);
+
+        vt.resize((3, 3));
+        assert_eq!(lines(&vt), ["c", "d", ""]);
+        assert_eq!(line(&vt, 0).len(), 1);
+    }
+
+    #[test]
+    fn empty_size() {
+        let mut vt = emulator((0, 0), b"a\nb\x1bD\x1bM\x1bE\x1b[L\x1b[M\x1b[S\x1b[T");
+        assert_eq!(vt.size(), (1, 1));
+        assert_eq!(lines(&vt), [""]);
+
+        vt.feed(b"\x1b[6n");
+        assert_eq!(vt.take_response(), b"\x1b[1;1R");
+    }
+
+    #[test]
+    fn resize_to_nothing_is_ignored() {
+        let mut vt = emulator((4, 4), b"a\r\nb\r\nc");
+        vt.resize((4, 0));
+        vt.resize((0, 4));
+        assert_eq!(vt.size(), (4, 4));
+        assert_eq!(vt.cursor(), (1, 2));
+
+        vt.feed(b"d");
+        assert_eq!(lines(&vt), ["a", "b", "cd", ""]);
+    }
+}